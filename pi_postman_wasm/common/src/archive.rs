@@ -0,0 +1,118 @@
+// common/src/archive.rs
+//
+// 원문(RFC822) 보관소: SHA-256 으로 주소를 매기는 디스크 저장소
+// 같은 메일은 한 번만 저장되고, 나중에 새 파서로 다시 파싱하거나 원문을 내보낼 때 씁니다.
+//
+// 경로: {ARCHIVE_DIR}/ab/cd/abcd….eml  (zstd 사용 시 .eml.zst)
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+lazy_static::lazy_static! {
+    static ref ARCHIVE: Option<RawArchive> = RawArchive::from_env();
+}
+
+pub struct RawArchive {
+    root: PathBuf,
+    /// Some(level) 이면 zstd 압축
+    zstd_level: Option<i32>,
+}
+
+impl RawArchive {
+    pub fn new(root: impl Into<PathBuf>, zstd_level: Option<i32>) -> Self {
+        RawArchive { root: root.into(), zstd_level }
+    }
+
+    /// ARCHIVE_DIR (없으면 보관 안 함), ARCHIVE_COMPRESS=zstd, ARCHIVE_ZSTD_LEVEL (기본 3)
+    pub fn from_env() -> Option<Self> {
+        let root = env::var("ARCHIVE_DIR").ok().filter(|s| !s.trim().is_empty())?;
+        let zstd_level = env::var("ARCHIVE_COMPRESS")
+            .ok()
+            .filter(|c| c.eq_ignore_ascii_case("zstd"))
+            .map(|_| {
+                env::var("ARCHIVE_ZSTD_LEVEL")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3)
+            });
+        Some(RawArchive::new(root, zstd_level))
+    }
+
+    fn dir_for(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[0..2]).join(&hash[2..4])
+    }
+
+    /// 원문 저장 후 SHA-256 (16진수) 반환. 이미 있으면 다시 쓰지 않음
+    pub fn store(&self, bytes: &[u8]) -> Result<String> {
+        let hash = hex::encode(Sha256::digest(bytes));
+        let dir = self.dir_for(&hash);
+        let plain = dir.join(format!("{}.eml", hash));
+        let packed = dir.join(format!("{}.eml.zst", hash));
+        if plain.exists() || packed.exists() {
+            return Ok(hash);
+        }
+
+        fs::create_dir_all(&dir).with_context(|| format!("{} 생성 실패", dir.display()))?;
+        let (path, data) = match self.zstd_level {
+            Some(level) => (packed, zstd::encode_all(bytes, level).context("zstd 압축 실패")?),
+            None => (plain, bytes.to_vec()),
+        };
+        // 쓰다가 죽어도 깨진 파일이 남지 않도록 임시 파일 → rename
+        let tmp = dir.join(format!(".{}.{}.tmp", hash, std::process::id()));
+        let mut file = fs::File::create(&tmp).with_context(|| format!("{} 생성 실패", tmp.display()))?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path).with_context(|| format!("{} 저장 실패", path.display()))?;
+        debug!("[Archive] {} 저장 ({}B → {}B)", hash, bytes.len(), data.len());
+        Ok(hash)
+    }
+
+    /// 해시로 원문 읽기 (압축 여부는 파일 확장자로 판단)
+    pub fn load(&self, hash: &str) -> Result<Vec<u8>> {
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(anyhow!("잘못된 원문 해시: {}", hash));
+        }
+        let hash = hash.to_ascii_lowercase();
+        let dir = self.dir_for(&hash);
+        let packed = dir.join(format!("{}.eml.zst", hash));
+        let bytes = if packed.exists() {
+            zstd::decode_all(fs::File::open(&packed)?).context("zstd 해제 실패")?
+        } else {
+            read(&dir.join(format!("{}.eml", hash)))?
+        };
+        // 디스크 손상 확인
+        if hex::encode(Sha256::digest(&bytes)) != hash {
+            return Err(anyhow!("원문 해시 불일치: {}", hash));
+        }
+        Ok(bytes)
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("원문 없음: {}", path.display()))
+}
+
+/// 전역 보관소에 저장 (ARCHIVE_DIR 미설정이거나 실패하면 None)
+pub fn store_raw(bytes: &[u8]) -> Option<String> {
+    let archive = ARCHIVE.as_ref()?;
+    match archive.store(bytes) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            warn!("[Archive] 원문 저장 실패: {}", e);
+            None
+        }
+    }
+}
+
+/// 전역 보관소에서 원문 읽기
+pub fn load_raw(hash: &str) -> Result<Vec<u8>> {
+    ARCHIVE
+        .as_ref()
+        .ok_or_else(|| anyhow!("ARCHIVE_DIR 미설정 — 원문 보관 비활성"))?
+        .load(hash)
+}
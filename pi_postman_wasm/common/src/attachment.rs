@@ -0,0 +1,300 @@
+// common/src/attachment.rs
+//
+// 첨부파일 디코딩 + 텍스트 추출 (PDF, DOCX, XLSX, CSV, TXT) + 이미지 메타데이터
+
+use crate::mime::decode_bytes;
+use calamine::{Reader, Xlsx};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{self, Cursor, Read};
+use std::panic::{self, AssertUnwindSafe};
+
+/// 첨부 하나에서 추출할 텍스트 최대 길이 (문자 수)
+const MAX_TEXT_PER_ATTACHMENT: usize = 20_000;
+/// 이보다 큰 파일은 텍스트 추출을 건너뜀
+const MAX_EXTRACT_BYTES: usize = 20 * 1024 * 1024;
+/// DOCX/XLSX 의 zip 항목 하나당 압축 해제 크기 상한 (압축 폭탄 방지)
+const MAX_ZIP_ENTRY_BYTES: u64 = 32 * 1024 * 1024;
+/// zip 전체 압축 해제 크기 상한
+const MAX_ZIP_TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+/// 첨부 텍스트 전체 예산 기본값 (ATTACHMENT_TEXT_BUDGET, 문자 수)
+pub const DEFAULT_ATTACHMENT_TEXT_BUDGET: usize = 4000;
+
+/// 이미지 첨부 메타데이터
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// 디코딩된 첨부파일
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub filename: String,
+    pub mime_type: String,
+    /// 전송 인코딩 해제 후 바이트 크기
+    pub size: usize,
+    /// 내용의 SHA-256 (16진수)
+    pub sha256: String,
+    /// 텍스트 추출 결과 (지원 형식만)
+    pub text: Option<String>,
+    pub image: Option<ImageInfo>,
+    /// 추출 실패 사유
+    pub error: Option<String>,
+}
+
+/// 텍스트 추출 대상 형식
+enum Kind {
+    Text,
+    Pdf,
+    Docx,
+    Xlsx,
+    Image,
+    Other,
+}
+
+impl Attachment {
+    /// 원본 바이트에서 첨부 정보 생성 (텍스트 추출 포함)
+    pub fn from_bytes(filename: &str, mime_type: &str, bytes: &[u8], charset: Option<&str>) -> Self {
+        let mut att = Attachment {
+            filename: filename.to_string(),
+            mime_type: mime_type.to_ascii_lowercase(),
+            size: bytes.len(),
+            sha256: hex::encode(Sha256::digest(bytes)),
+            text: None,
+            image: None,
+            error: None,
+        };
+
+        let kind = detect_kind(&att.filename, &att.mime_type);
+        if bytes.len() > MAX_EXTRACT_BYTES && !matches!(kind, Kind::Image | Kind::Other) {
+            att.error = Some(format!("{}B 초과 — 텍스트 추출 생략", MAX_EXTRACT_BYTES));
+            return att;
+        }
+
+        let result = match kind {
+            Kind::Text => Ok(Some(decode_bytes(bytes, charset).text)),
+            Kind::Pdf => extract_pdf(bytes).map(Some),
+            Kind::Docx => extract_docx(bytes).map(Some),
+            Kind::Xlsx => extract_xlsx(bytes).map(Some),
+            Kind::Image => {
+                match imagesize::blob_size(bytes) {
+                    Ok(dim) => {
+                        att.image = Some(ImageInfo { width: dim.width, height: dim.height })
+                    }
+                    Err(e) => att.error = Some(format!("이미지 헤더 파싱 실패: {}", e)),
+                }
+                Ok(None)
+            }
+            Kind::Other => Ok(None),
+        };
+
+        match result {
+            Ok(Some(text)) => att.text = Some(truncate_chars(text.trim(), MAX_TEXT_PER_ATTACHMENT)),
+            Ok(None) => {}
+            Err(e) => att.error = Some(e),
+        }
+        att
+    }
+}
+
+fn detect_kind(filename: &str, mime_type: &str) -> Kind {
+    let ext = filename
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match (mime_type, ext.as_str()) {
+        ("application/pdf", _) | (_, "pdf") => Kind::Pdf,
+        ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", _)
+        | (_, "docx") => Kind::Docx,
+        ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", _)
+        | (_, "xlsx") => Kind::Xlsx,
+        (_, "txt" | "csv" | "tsv" | "md" | "log" | "json" | "xml") => Kind::Text,
+        (m, _) if m.starts_with("text/") => Kind::Text,
+        (m, _) if m.starts_with("image/") => Kind::Image,
+        (_, "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "tiff") => Kind::Image,
+        _ => Kind::Other,
+    }
+}
+
+/// PDF: 깨진 파일에서 pdf_extract 가 패닉하므로 잡아서 오류로 바꿈
+fn extract_pdf(bytes: &[u8]) -> Result<String, String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| pdf_extract::extract_text_from_mem(bytes))) {
+        Ok(result) => result.map_err(|e| format!("PDF 추출 실패: {}", e)),
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "알 수 없는 오류".into());
+            Err(format!("PDF 추출 실패 (손상된 파일): {}", msg))
+        }
+    }
+}
+
+/// zip 항목을 상한까지만 읽음 (넘으면 오류)
+fn read_capped(entry: impl Read, name: &str) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    entry
+        .take(MAX_ZIP_ENTRY_BYTES + 1)
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("{} 읽기 실패: {}", name, e))?;
+    if buf.len() as u64 > MAX_ZIP_ENTRY_BYTES {
+        return Err(format!("{} 압축 해제 크기가 {}B 초과", name, MAX_ZIP_ENTRY_BYTES));
+    }
+    Ok(buf)
+}
+
+/// 모든 항목의 실제 압축 해제 크기 확인 (calamine 은 크기 제한 없이 읽으므로 먼저 검사)
+fn check_zip_sizes(bytes: &[u8]) -> Result<(), String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("zip 열기 실패: {}", e))?;
+    let mut total = 0u64;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| format!("zip 항목 읽기 실패: {}", e))?;
+        let name = entry.name().to_string();
+        let n = io::copy(&mut (&mut entry).take(MAX_ZIP_ENTRY_BYTES + 1), &mut io::sink())
+            .map_err(|e| format!("{} 읽기 실패: {}", name, e))?;
+        if n > MAX_ZIP_ENTRY_BYTES {
+            return Err(format!("{} 압축 해제 크기가 {}B 초과", name, MAX_ZIP_ENTRY_BYTES));
+        }
+        total += n;
+        if total > MAX_ZIP_TOTAL_BYTES {
+            return Err(format!("압축 해제 전체 크기가 {}B 초과", MAX_ZIP_TOTAL_BYTES));
+        }
+    }
+    Ok(())
+}
+
+/// DOCX: word/document.xml 의 텍스트 런(<w:t>) 을 문단(<w:p>) 단위로 합침
+fn extract_docx(bytes: &[u8]) -> Result<String, String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("DOCX 열기 실패: {}", e))?;
+    let entry = zip
+        .by_name("word/document.xml")
+        .map_err(|e| format!("DOCX 본문 없음: {}", e))?;
+    let xml = String::from_utf8_lossy(&read_capped(entry, "DOCX 본문")?).into_owned();
+
+    let mut out = String::new();
+    let mut rest = xml.as_str();
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else { break };
+        let tag = &rest[start + 1..start + end];
+        let after = &rest[start + end + 1..];
+        if tag.starts_with("w:t") && !tag.ends_with('/') && (tag.len() == 3 || tag.as_bytes()[3] == b' ') {
+            if let Some(close) = after.find("</w:t>") {
+                out.push_str(&unescape_xml(&after[..close]));
+                rest = &after[close..];
+                continue;
+            }
+        } else if tag == "/w:p" {
+            out.push('\n');
+        } else if tag == "w:tab/" {
+            out.push('\t');
+        } else if tag == "w:br/" {
+            out.push('\n');
+        }
+        rest = after;
+    }
+    Ok(out)
+}
+
+/// XLSX: 모든 시트를 "시트명" 제목 + 탭 구분 행으로 변환
+fn extract_xlsx(bytes: &[u8]) -> Result<String, String> {
+    check_zip_sizes(bytes)?;
+    let mut book: Xlsx<_> =
+        Xlsx::new(Cursor::new(bytes.to_vec())).map_err(|e| format!("XLSX 열기 실패: {}", e))?;
+    let mut out = String::new();
+    for name in book.sheet_names().to_vec() {
+        let Ok(range) = book.worksheet_range(&name) else { continue };
+        out.push_str(&format!("[{}]\n", name));
+        for row in range.rows() {
+            let cells: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+            if cells.iter().all(|c| c.is_empty()) {
+                continue;
+            }
+            out.push_str(&cells.join("\t"));
+            out.push('\n');
+            if out.len() > MAX_TEXT_PER_ATTACHMENT * 4 {
+                return Ok(out);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn truncate_chars(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &s[..idx]),
+        None => s.to_string(),
+    }
+}
+
+/// 첨부 텍스트 전체 예산 (ATTACHMENT_TEXT_BUDGET, 기본 4000자)
+pub fn attachment_text_budget() -> usize {
+    std::env::var("ATTACHMENT_TEXT_BUDGET")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_ATTACHMENT_TEXT_BUDGET)
+}
+
+/// 분류 프롬프트에 붙일 첨부 요약 (전체 `budget` 문자 이내)
+///
+/// 파일명/형식/크기는 항상 넣고, 추출된 텍스트는 남은 예산만큼만 넣습니다.
+pub fn attachments_for_prompt(attachments: &[Attachment], budget: usize) -> String {
+    let mut out = String::new();
+    let mut remaining = budget;
+    for att in attachments {
+        let mut line = format!("[첨부] {} ({}, {}B)", att.filename, att.mime_type, att.size);
+        if let Some(img) = &att.image {
+            line.push_str(&format!(" {}x{}", img.width, img.height));
+        }
+        line.push('\n');
+        if line.chars().count() > remaining {
+            break;
+        }
+        remaining -= line.chars().count();
+        out.push_str(&line);
+
+        if let Some(text) = att.text.as_deref().filter(|t| !t.is_empty()) {
+            if remaining < 20 {
+                continue;
+            }
+            // 말줄임표(…)와 줄바꿈 자리 확보
+            let snippet = truncate_chars(text, remaining - 2);
+            remaining = remaining.saturating_sub(snippet.chars().count() + 1);
+            out.push_str(&snippet);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// 저장/검색용 첨부 텍스트 ("[파일명]\n텍스트" 를 이어 붙이되 전체 `budget` 문자 이내)
+///
+/// 분류 프롬프트와 같은 예산을 쓰므로 큰 첨부가 DB 와 검색 색인을 부풀리지 않습니다.
+pub fn attachments_text(attachments: &[Attachment], budget: usize) -> String {
+    let mut out = String::new();
+    let mut remaining = budget;
+    for att in attachments {
+        let Some(text) = att.text.as_deref().filter(|t| !t.is_empty()) else { continue };
+        let head = format!("{}[{}]\n", if out.is_empty() { "" } else { "\n\n" }, att.filename);
+        let head_len = head.chars().count();
+        if head_len + 20 > remaining {
+            break;
+        }
+        remaining -= head_len;
+        // 말줄임표(…) 자리 확보
+        let snippet = truncate_chars(text, remaining - 1);
+        remaining = remaining.saturating_sub(snippet.chars().count());
+        out.push_str(&head);
+        out.push_str(&snippet);
+    }
+    out
+}
@@ -24,28 +24,82 @@ struct Classification {
     confidence: f32,
 }
 
-/// 이메일 제목/본문을 AI로 분류 (정규식 + 타임아웃 개선)
+/// 분류용 시스템 프롬프트 (일괄 처리 API 에서도 같은 문구 사용)
+pub const SYSTEM_PROMPT: &str = "당신은 이메일 스팸 분류 전문가입니다. \
+     결과는 정확히 JSON 하나만, 예시처럼 응답하세요:\n\
+     {\"category\":\"SPAM\",\"confidence\":0.87}";
+
+/// 분류용 사용자 메시지
+pub fn user_prompt(subject: &str, body: &str) -> String {
+    format!("제목: {}\n본문:\n{}", subject, body)
+}
+
+/// 모델 응답에서 분류 JSON 추출 (```json 블록이 있으면 그 안만)
+#[cfg(feature = "native")]
+pub fn parse_classification(full: &str) -> Result<(String, f32)> {
+    let re = Regex::new(r#"```json\s*([\s\S]*?)\s*```"#).unwrap();
+    let json_block = re
+        .captures(full)
+        .and_then(|cap| cap.get(1).map(|m| m.as_str()))
+        .unwrap_or(full);
+
+    match serde_json::from_str::<Classification>(json_block) {
+        Ok(c) => Ok((c.category, c.confidence)),
+        Err(e) => {
+            error!("JSON 파싱 실패: {} | 추출된 JSON: {} | 전체 응답: {}", e, json_block, full);
+            Err(anyhow!("JSON 파싱 실패: {}", e))
+        }
+    }
+}
+
+/// OpenAI 호환 API 설정 (환경 변수 또는 AI 세션에서 옴)
+#[cfg(feature = "native")]
+#[derive(Clone)]
+pub struct OpenAiConfig {
+    pub api_key: String,
+    pub model: String,
+    pub base_url: String,
+    pub timeout: Duration,
+}
+
+#[cfg(feature = "native")]
+impl OpenAiConfig {
+    /// OPENAI_API_KEY (필수), OPENAI_MODEL, OPENAI_API_URL, OPENAI_TIMEOUT (초)
+    pub fn from_env() -> Result<Self> {
+        let api_key = env::var("OPENAI_API_KEY")
+            .map_err(|_| anyhow!("환경변수 OPENAI_API_KEY가 설정되어야 합니다"))?;
+        let model = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4".to_string());
+        let base_url = env::var("OPENAI_API_URL")
+            .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+        let to_sec: u64 = env::var("OPENAI_TIMEOUT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+        Ok(OpenAiConfig { api_key, model, base_url, timeout: Duration::from_secs(to_sec) })
+    }
+
+    /// 끝의 / 를 뗀 API 주소
+    pub fn base(&self) -> &str {
+        self.base_url.trim_end_matches('/')
+    }
+}
+
+/// 이메일 제목/본문을 AI로 분류 (환경 변수 설정 + 타임아웃)
 #[cfg(feature = "native")]
 pub async fn classify_via_openai(subject: &str, body: &str) -> Result<(String, f32)> {
-    let api_key = env::var("OPENAI_API_KEY")
-        .map_err(|_| anyhow!("환경변수 OPENAI_API_KEY가 설정되어야 합니다"))?;
-    let model = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4".to_string());
-    let api_url = env::var("OPENAI_API_URL")
-        .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
-    let to_sec: u64 = env::var("OPENAI_TIMEOUT")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(60);
+    let config = OpenAiConfig::from_env()?;
+    timeout(config.timeout, classify_with(&config, subject, body))
+        .await
+        .map_err(|_| anyhow!("OpenAI 호출 타임아웃 ({}초)", config.timeout.as_secs()))?
+}
 
-    let creds = Credentials::new(api_key, api_url);
+/// 주어진 설정으로 분류 (타임아웃은 호출하는 쪽에서 적용)
+#[cfg(feature = "native")]
+pub async fn classify_with(config: &OpenAiConfig, subject: &str, body: &str) -> Result<(String, f32)> {
+    let creds = Credentials::new(config.api_key.clone(), config.base_url.clone());
     let system = ChatCompletionMessage {
         role: ChatCompletionMessageRole::System,
-        content: Some(
-            "당신은 이메일 스팸 분류 전문가입니다. \
-             결과는 정확히 JSON 하나만, 예시처럼 응답하세요:\n\
-             {\"category\":\"SPAM\",\"confidence\":0.87}"
-                .to_string(),
-        ),
+        content: Some(SYSTEM_PROMPT.to_string()),
         name: None,
         function_call: None,
         tool_call_id: None,
@@ -53,20 +107,18 @@ pub async fn classify_via_openai(subject: &str, body: &str) -> Result<(String, f
     };
     let user = ChatCompletionMessage {
         role: ChatCompletionMessageRole::User,
-        content: Some(format!("제목: {}\n본문:\n{}", subject, body)),
+        content: Some(user_prompt(subject, body)),
         name: None,
         function_call: None,
         tool_call_id: None,
         tool_calls: None,
     };
 
-    info!("[AI] model={} timeout={}s subject='{}' body_len={}", model, to_sec, subject, body.len());
-    let send_fut = ChatCompletion::builder(&model, vec![system, user])
+    info!("[AI] model={} subject='{}' body_len={}", config.model, subject, body.len());
+    let response = ChatCompletion::builder(&config.model, vec![system, user])
         .credentials(creds)
-        .create();
-    let response = timeout(Duration::from_secs(to_sec), send_fut)
+        .create()
         .await
-        .map_err(|_| anyhow!("OpenAI 호출 타임아웃 ({}초)", to_sec))?
         .map_err(|e| anyhow!("AI 호출 실패: {}", e))?;
 
     let full = response
@@ -75,18 +127,5 @@ pub async fn classify_via_openai(subject: &str, body: &str) -> Result<(String, f
         .and_then(|c| c.message.content.clone())
         .unwrap_or_default();
 
-    // ```json ... ``` 사이 콘텐츠 추출
-    let re = Regex::new(r#"```json\s*([\s\S]*?)\s*```"#).unwrap();
-    let json_block = re
-        .captures(&full)
-        .and_then(|cap| cap.get(1).map(|m| m.as_str()))
-        .unwrap_or(&full);
-
-    match serde_json::from_str::<Classification>(json_block) {
-        Ok(c) => Ok((c.category, c.confidence)),
-        Err(e) => {
-            error!("JSON 파싱 실패: {} | 추출된 JSON: {} | 전체 응답: {}", e, json_block, full);
-            Err(anyhow!("JSON 파싱 실패: {}", e))
-        }
-    }
+    parse_classification(&full)
 }
@@ -14,18 +14,24 @@ pub async fn send_discord_alert(
     subject: &str,
     sender: &str,
     category: &str,
+    link: Option<&str>,
 ) -> Result<(), reqwest::Error> {
     let is_spam = category.eq_ignore_ascii_case("SPAM");
     let prefix = if is_spam { "[스팸] " } else { "" };
+    let link_info = match link {
+        Some(url) => format!("\n바로가기: <{}>", url),
+        None => String::new(),
+    };
     let content = format!(
         "{prefix}📬 메일 알림\n\
          제목: {subject}\n\
          보낸이: {sender}\n\
-         분류: {category}",
+         분류: {category}{link_info}",
         prefix = prefix,
         subject = subject,
         sender = sender,
-        category = category
+        category = category,
+        link_info = link_info
     );
 
     let client = Client::new();
@@ -1,17 +1,12 @@
 // common/src/email.rs
 
 use anyhow::{Result, anyhow};
+use crate::headers::EmailHeaders;
+use crate::store::store;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-lazy_static::lazy_static! {
-    static ref EMAIL_STORE: Arc<Mutex<HashMap<String, Email>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Email {
     pub id: String,
@@ -22,6 +17,34 @@ pub struct Email {
     pub received_at: DateTime<Utc>,
     pub category: Option<String>,
     pub ai_processed: bool,
+    /// 파싱된 전체 헤더 (API 로 직접 수신한 메일은 비어 있음)
+    #[serde(default)]
+    pub headers: EmailHeaders,
+    /// 원문 보관소의 SHA-256 (원문 MIME 이 있는 메일만)
+    #[serde(default)]
+    pub raw_sha256: Option<String>,
+    /// 첨부파일에서 추출한 텍스트 (검색용)
+    #[serde(default)]
+    pub attachment_text: String,
+}
+
+impl Email {
+    /// 새 메일 (ID 발급, 수신 시각은 현재)
+    pub fn new(from: &str, to: &str, subject: &str, body: &str) -> Self {
+        Email {
+            id: Uuid::new_v4().to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            received_at: Utc::now(),
+            category: None,
+            ai_processed: false,
+            headers: EmailHeaders::default(),
+            raw_sha256: None,
+            attachment_text: String::new(),
+        }
+    }
 }
 
 pub async fn process_incoming_email(
@@ -30,32 +53,36 @@ pub async fn process_incoming_email(
     subject: &str,
     body: &str,
 ) -> Result<String> {
-    let email_id = Uuid::new_v4().to_string();
-    let email = Email {
-        id: email_id.clone(),
-        from: from.to_string(),
-        to: to.to_string(),
-        subject: subject.to_string(),
-        body: body.to_string(),
-        received_at: Utc::now(),
-        category: None,
-        ai_processed: false,
-    };
-    {
-        let mut store = EMAIL_STORE
-            .lock()
-            .map_err(|_| anyhow!("이메일 저장소 잠금 실패"))?;
-        store.insert(email_id.clone(), email);
-    }
-    Ok(email_id)
+    store_incoming(Email::new(from, to, subject, body)).await
+}
+
+/// 헤더/첨부 등을 채운 메일 저장 (원문 MIME 에서 온 메일)
+pub async fn store_incoming(email: Email) -> Result<String> {
+    store().insert(&email)?;
+    #[cfg(feature = "native")]
+    crate::events::publish(crate::events::EventKind::Received, &email, None);
+    Ok(email.id)
+}
+
+/// 저장된 이메일 수정 후 수정된 값 반환 (읽기~쓰기 사이에 다른 수정이 끼어들지 않음)
+pub fn update_email<F: FnOnce(&mut Email)>(email_id: &str, f: F) -> Result<Email> {
+    store().modify(email_id, Box::new(f))
+}
+
+/// 분류 결과 기록
+pub fn set_category(email_id: &str, category: &str) -> Result<()> {
+    #[cfg_attr(not(feature = "native"), allow(unused_variables))]
+    let email = update_email(email_id, |email| {
+        email.category = Some(category.to_string());
+        email.ai_processed = true;
+    })?;
+    #[cfg(feature = "native")]
+    crate::events::publish(crate::events::EventKind::Classified, &email, None);
+    Ok(())
 }
 
 pub fn get_email(email_id: &str) -> Result<Email> {
-    let store = EMAIL_STORE
-        .lock()
-        .map_err(|_| anyhow!("이메일 저장소 잠금 실패"))?;
-    store
-        .get(email_id)
-        .cloned()
+    store()
+        .get(email_id)?
         .ok_or_else(|| anyhow!("이메일을 찾을 수 없음: {}", email_id))
 }
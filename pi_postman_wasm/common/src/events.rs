@@ -0,0 +1,180 @@
+// common/src/events.rs
+//
+// 메일 처리 이벤트 버스 (수신 / 분류 / 알림 / 실패)
+// 최근 이벤트는 링 버퍼(EVENT_BUFFER_SIZE, 기본 1024)에 남겨 Last-Event-ID 로 이어 받을 수 있고,
+// 실시간 구독은 broadcast 채널로 전달합니다.
+// 이벤트 ID 는 프로세스 시작 시각에서 출발하므로, 재시작 전 ID 로 이어 받으면
+// 그 사이 이벤트가 없어진 것으로 보고 gap 을 알립니다.
+
+use crate::email::Email;
+use crate::store::store;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+lazy_static::lazy_static! {
+    static ref BUS: EventBus = EventBus::new(
+        std::env::var("EVENT_BUFFER_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(1024)
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Received,
+    Classified,
+    Notified,
+    Failed,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Received => "received",
+            EventKind::Classified => "classified",
+            EventKind::Notified => "notified",
+            EventKind::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "received" => Some(EventKind::Received),
+            "classified" => Some(EventKind::Classified),
+            "notified" => Some(EventKind::Notified),
+            "failed" => Some(EventKind::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MailEvent {
+    /// 단조 증가 (SSE id). 프로세스마다 시작 시각 기준 값에서 출발
+    pub id: u64,
+    pub kind: EventKind,
+    pub email_id: String,
+    pub from: String,
+    pub subject: String,
+    pub category: Option<String>,
+    /// 실패 사유 등
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// 구독 필터 (비어 있으면 전부)
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    /// 소문자 카테고리 목록
+    pub categories: Vec<String>,
+    pub kinds: Vec<EventKind>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &MailEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+            && (self.categories.is_empty()
+                || event
+                    .category
+                    .as_deref()
+                    .is_some_and(|c| self.categories.iter().any(|f| f.eq_ignore_ascii_case(c))))
+    }
+}
+
+/// 구독 시작 시점의 상태
+pub struct Subscription {
+    /// 요청한 ID 이후로 버퍼에 남아 있는 이벤트
+    pub replay: Vec<MailEvent>,
+    /// 요청한 ID 이후 일부가 이미 버퍼에서 밀려났으면 true
+    pub gap: bool,
+    pub rx: broadcast::Receiver<MailEvent>,
+}
+
+struct Ring {
+    next_id: u64,
+    events: VecDeque<MailEvent>,
+}
+
+struct EventBus {
+    ring: Mutex<Ring>,
+    capacity: usize,
+    tx: broadcast::Sender<MailEvent>,
+}
+
+/// 첫 이벤트 ID: 시작 시각(초) << 20
+///
+/// 초당 2^20 건 미만이면 이전 프로세스의 어떤 ID 보다도 크고, JSON 숫자로도 정확히 표현됩니다.
+fn epoch_id() -> u64 {
+    (Utc::now().timestamp().max(0) as u64) << 20
+}
+
+impl EventBus {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(16);
+        let (tx, _) = broadcast::channel(capacity);
+        let ring = Ring { next_id: epoch_id() + 1, events: VecDeque::with_capacity(capacity) };
+        EventBus { ring: Mutex::new(ring), capacity, tx }
+    }
+}
+
+/// 버퍼에서 `after` 이후 이벤트 (gap: 그 사이 일부가 밀려났는지)
+fn replay_locked(ring: &Ring, after: u64) -> (Vec<MailEvent>, bool) {
+    let oldest = ring.events.front().map(|e| e.id).unwrap_or(ring.next_id);
+    let gap = after + 1 < oldest && after + 1 < ring.next_id;
+    (ring.events.iter().filter(|e| e.id > after).cloned().collect(), gap)
+}
+
+/// 이벤트 발행
+pub fn publish(kind: EventKind, email: &Email, detail: Option<String>) {
+    let mut ring = BUS.ring.lock().unwrap_or_else(|e| e.into_inner());
+    let event = MailEvent {
+        id: ring.next_id,
+        kind,
+        email_id: email.id.clone(),
+        from: email.from.clone(),
+        subject: email.subject.clone(),
+        category: email.category.clone(),
+        detail,
+        at: Utc::now(),
+    };
+    ring.next_id += 1;
+    if ring.events.len() == BUS.capacity {
+        ring.events.pop_front();
+    }
+    ring.events.push_back(event.clone());
+    // 구독자가 없으면 Err — 무시
+    let _ = BUS.tx.send(event);
+}
+
+/// 저장소에서 메일을 찾아 발행 (없으면 ID 만 채움)
+pub fn publish_id(kind: EventKind, email_id: &str, detail: Option<String>) {
+    match store().get(email_id) {
+        Ok(Some(email)) => publish(kind, &email, detail),
+        _ => {
+            let mut email = Email::new("", "", "", "");
+            email.id = email_id.to_string();
+            publish(kind, &email, detail)
+        }
+    }
+}
+
+/// 구독 (after 가 있으면 그 이후 버퍼 이벤트를 먼저 재생)
+///
+/// 잠금 안에서 재생 목록과 수신기를 함께 만들어 중복이나 누락이 없게 합니다.
+pub fn subscribe(after: Option<u64>) -> Subscription {
+    let ring = BUS.ring.lock().unwrap_or_else(|e| e.into_inner());
+    let (replay, gap) = match after {
+        Some(after) => replay_locked(&ring, after),
+        None => (Vec::new(), false),
+    };
+    Subscription { replay, gap, rx: BUS.tx.subscribe() }
+}
+
+/// 수신기가 밀렸을 때 버퍼에서 다시 채움
+pub fn replay_since(after: u64) -> (Vec<MailEvent>, bool) {
+    let ring = BUS.ring.lock().unwrap_or_else(|e| e.into_inner());
+    replay_locked(&ring, after)
+}
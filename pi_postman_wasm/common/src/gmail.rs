@@ -1,13 +1,68 @@
 // common/src/gmail.rs
 
+use crate::archive::store_raw;
+use crate::attachment::{attachment_text_budget, attachments_text, Attachment};
+use crate::email::Email;
+use crate::headers::EmailHeaders;
+use crate::html::html_to_text;
+use crate::mime::{
+    decode_base64_lenient, decode_bytes, decode_header, decode_part_body,
+    decode_quoted_printable, unfold,
+};
+use base64::alphabet::IMAP_MUTF7;
+use base64::engine::general_purpose::{GeneralPurpose, NO_PAD};
+use base64::Engine;
 use imap::Session;
-use mailparse::{parse_mail, ParsedMail, MailHeaderMap};  // ← MailHeaderMap 추가
+use imap_proto::types::{BodyStructure, ContentEncoding, SectionPath};
+use mailparse::{parse_mail, DispositionType, ParsedMail, MailHeaderMap};  // ← MailHeaderMap 추가
 use native_tls::TlsConnector;
-use scraper::Html;
-use std::net::TcpStream;
+use regex::Regex;
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::net::{TcpStream, ToSocketAddrs};
 use std::io;
+use std::time::Duration;
 use imap::error::Error as ImapError;
+use lazy_static::lazy_static;
+use tracing::{debug, info, warn};
 
+/// 이 크기를 넘는 메일은 전체를 받지 않고 BODYSTRUCTURE 기반 부분 fetch 사용
+const DEFAULT_MAX_FULL_FETCH: u32 = 10 * 1024 * 1024;
+/// 부분 fetch 시 본문 파트에서 가져올 최대 바이트
+const PARTIAL_BODY_BYTES: u32 = 64 * 1024;
+/// 메일함 이름용 modified base64 (',' 사용, 패딩 없음)
+const MUTF7: GeneralPurpose = GeneralPurpose::new(&IMAP_MUTF7, NO_PAD);
+
+lazy_static! {
+    /// UID FETCH 응답의 X-GM-MSGID / X-GM-THRID 값
+    static ref GM_MSGID: Regex = Regex::new(r"X-GM-MSGID (\d+)").unwrap();
+    static ref GM_THRID: Regex = Regex::new(r"X-GM-THRID (\d+)").unwrap();
+}
+
+/// 메일 본문을 가져오는 데 성공한 방식
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchStrategy {
+    /// BODY.PEEK[] 전체 원문
+    Full,
+    /// BODY.PEEK[HEADER] + BODY.PEEK[TEXT] 를 따로 받아 합침
+    HeaderAndText,
+    /// BODY.PEEK[HEADER] + BODYSTRUCTURE 로 찾은 본문 파트 일부
+    Partial,
+}
+
+impl std::fmt::Display for FetchStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            FetchStrategy::Full => "full",
+            FetchStrategy::HeaderAndText => "header+text",
+            FetchStrategy::Partial => "partial",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Clone)]
 pub struct GmailConfig {
     pub email: String,
     pub password: String,
@@ -15,124 +70,906 @@ pub struct GmailConfig {
 
 pub struct ParsedEmail {
     pub uid: String,
+    /// 메일을 가져온 메일함 (UID 는 메일함 안에서만 유일)
+    pub mailbox: String,
     pub subject: String,
     pub from: String,
     pub body: String,
-    pub attachments: Vec<String>,
+    /// HTML 본문에서 추출한 링크 (본문의 [n] 표시와 순서 일치)
+    pub links: Vec<String>,
+    /// 디코딩된 첨부파일 (형식, 크기, 해시, 추출 텍스트)
+    pub attachments: Vec<Attachment>,
     pub gmail_link: String,
+    /// Message-ID 헤더 (꺾쇠 괄호 제외)
+    pub message_id: Option<String>,
+    /// Gmail 고유 메시지 ID (X-GM-MSGID)
+    pub gm_msgid: Option<u64>,
+    /// Gmail 스레드 ID (X-GM-THRID)
+    pub gm_thrid: Option<u64>,
+    /// To/Cc/Date/List-Id 등 파싱된 전체 헤더
+    pub headers: EmailHeaders,
+    /// 디코딩 중 발생한 경고 (문자셋 추정, encoded-word 복구 등)
+    pub parse_warnings: Vec<String>,
+    /// 본문을 가져온 fetch 방식
+    pub fetch_strategy: FetchStrategy,
+    /// 원문 보관소의 SHA-256 (ARCHIVE_DIR 설정 시, 부분 fetch 는 보관하지 않음)
+    pub raw_sha256: Option<String>,
+}
+
+impl ParsedEmail {
+    /// 저장용 Email 로 변환 (새 ID 발급)
+    pub fn to_email(&self) -> Email {
+        let to = self
+            .headers
+            .to
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut email = Email::new(&self.from, &to, &self.subject, &self.body);
+        email.headers = self.headers.clone();
+        email.raw_sha256 = self.raw_sha256.clone();
+        email.attachment_text = self.attachment_text();
+        email
+    }
+
+    /// 저장/검색용 첨부 텍스트 (ATTACHMENT_TEXT_BUDGET 이내)
+    pub fn attachment_text(&self) -> String {
+        attachments_text(&self.attachments, attachment_text_budget())
+    }
+}
+
+pub type GmailSession = Session<native_tls::TlsStream<TcpStream>>;
+
+/// IMAP 타임아웃 설정
+#[derive(Clone, Copy, Debug)]
+pub struct ImapTimeouts {
+    /// TCP 연결 + TLS + 로그인
+    pub connect: Duration,
+    /// 소켓 읽기/쓰기 1회
+    pub read: Duration,
+    /// AsyncSession::run 작업 하나 전체 (메일 하나 fetch, 검색 등 IMAP 명령 단위)
+    pub operation: Duration,
+}
+
+impl ImapTimeouts {
+    /// IMAP_CONNECT_TIMEOUT / IMAP_READ_TIMEOUT / IMAP_OP_TIMEOUT (초)
+    pub fn from_env() -> Self {
+        let secs = |key: &str, default: u64| {
+            Duration::from_secs(
+                env::var(key)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(default),
+            )
+        };
+        ImapTimeouts {
+            connect: secs("IMAP_CONNECT_TIMEOUT", 15),
+            read: secs("IMAP_READ_TIMEOUT", 60),
+            operation: secs("IMAP_OP_TIMEOUT", 300),
+        }
+    }
 }
 
-pub async fn connect_to_gmail(
-    config: &GmailConfig,
-) -> imap::error::Result<Session<native_tls::TlsStream<TcpStream>>> {
-    let tls = TlsConnector::builder().build().unwrap();
-    let tcp = TcpStream::connect(("imap.gmail.com", 993))?;
+fn io_error(kind: io::ErrorKind, msg: impl Into<String>) -> ImapError {
+    ImapError::Io(io::Error::new(kind, msg.into()))
+}
+
+/// Gmail 접속 (블로킹 작업은 tokio 블로킹 풀에서 실행)
+pub async fn connect_to_gmail(config: &GmailConfig) -> imap::error::Result<GmailSession> {
+    let config = config.clone();
+    let timeouts = ImapTimeouts::from_env();
+    let job = tokio::task::spawn_blocking(move || connect_blocking(&config, &timeouts));
+    // 로그인 응답 대기까지 포함해 여유를 둠
+    match tokio::time::timeout(timeouts.connect + timeouts.read, job).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(io_error(io::ErrorKind::Other, format!("IMAP 연결 작업 실패: {}", e))),
+        Err(_) => Err(io_error(io::ErrorKind::TimedOut, "IMAP 연결 타임아웃")),
+    }
+}
+
+/// 타임아웃이 걸린 블로킹 접속
+fn connect_blocking(config: &GmailConfig, timeouts: &ImapTimeouts) -> imap::error::Result<GmailSession> {
+    let addr = ("imap.gmail.com", 993)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io_error(io::ErrorKind::NotFound, "imap.gmail.com 주소 조회 실패"))?;
+    let tcp = TcpStream::connect_timeout(&addr, timeouts.connect)?;
+    tcp.set_read_timeout(Some(timeouts.read))?;
+    tcp.set_write_timeout(Some(timeouts.read))?;
+
+    let tls = TlsConnector::builder()
+        .build()
+        .map_err(|e| io_error(io::ErrorKind::Other, e.to_string()))?;
     let tls_stream = tls
-    .connect("imap.gmail.com", tcp)
-    .map_err(|e| ImapError::Io(io::Error::new(io::ErrorKind::Other, e)))?;
-    let mut client = imap::Client::new(tls_stream);
+        .connect("imap.gmail.com", tcp)
+        .map_err(|e| io_error(io::ErrorKind::Other, e.to_string()))?;
+    let client = imap::Client::new(tls_stream);
     let session = client
         .login(&config.email, &config.password)
         .map_err(|e| e.0)?;
     Ok(session)
 }
 
-pub fn fetch_unseen_emails(
-    session: &mut Session<native_tls::TlsStream<TcpStream>>,
-) -> imap::error::Result<Vec<ParsedEmail>> {
-    session.select("INBOX")?;
-    let uids = session.search("UNSEEN")?;  // mut 제거
-    let mut out = Vec::new();
-
-    for uid in uids {
-        let fetches = session.fetch(uid.to_string(), "RFC822")?;
-        for fetch in fetches.iter() {
-            if let Some(bytes) = fetch.body() {
-                if let Ok(parsed) = parse_mail(bytes) {
-                    // header 추출: get_first_value 는 MailHeaderMap 트레잇에서 제공됩니다
-                    let subject = parsed
-                        .headers
-                        .get_first_value("Subject")
-                        .unwrap_or_else(|| "(제목 없음)".into());
-                    let from = parsed
-                        .headers
-                        .get_first_value("From")
-                        .unwrap_or_else(|| "(보낸 사람 없음)".into());
-
-                    // 본문 추출
-                    fn extract_plain_body(part: &ParsedMail) -> Option<String> {
-                        if part.subparts.is_empty()
-                            && part.ctype.mimetype.eq_ignore_ascii_case("text/plain")
-                        {
-                            return part.get_body().ok();
-                        }
-                        for sub in &part.subparts {
-                            if let Some(b) = extract_plain_body(sub) {
-                                return Some(b);
-                            }
-                        }
-                        if part.subparts.is_empty()
-                            && part.ctype.mimetype.eq_ignore_ascii_case("text/html")
-                        {
-                            if let Ok(html) = part.get_body() {
-                                let fragment = Html::parse_fragment(&html);
-                                let text = fragment
-                                    .root_element()
-                                    .text()
-                                    .collect::<Vec<_>>()
-                                    .join("");
-                                return Some(text);
-                            }
-                        }
-                        None
+/// 블로킹 IMAP 세션을 tokio 블로킹 풀에서 돌리는 래퍼
+///
+/// 작업마다 세션을 블로킹 스레드로 옮겼다가 돌려받으므로
+/// 느린 Gmail 응답이 tokio 워커 스레드를 막지 않습니다.
+pub struct AsyncSession {
+    inner: Option<GmailSession>,
+    timeouts: ImapTimeouts,
+}
+
+impl AsyncSession {
+    pub async fn connect(config: &GmailConfig) -> imap::error::Result<Self> {
+        let session = connect_to_gmail(config).await?;
+        Ok(AsyncSession { inner: Some(session), timeouts: ImapTimeouts::from_env() })
+    }
+
+    /// 세션으로 블로킹 작업 실행 (작업 전체에 IMAP_OP_TIMEOUT 적용)
+    ///
+    /// 타임아웃이나 패닉이 나면 세션은 버려지고 이후 호출은 오류를 반환합니다.
+    /// 타임아웃 뒤에도 블로킹 작업 자체는 끝까지 돌 수 있으므로, 메일함 전체처럼 긴 작업이나
+    /// 결과를 돌려받기 전에 상태를 바꾸는 작업(\Seen 표시 등)을 한 번에 넣지 마세요.
+    pub async fn run<R, F>(&mut self, f: F) -> imap::error::Result<R>
+    where
+        F: FnOnce(&mut GmailSession) -> imap::error::Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let mut session = self
+            .inner
+            .take()
+            .ok_or_else(|| io_error(io::ErrorKind::NotConnected, "IMAP 세션이 이미 끊어졌습니다"))?;
+        let job = tokio::task::spawn_blocking(move || {
+            let result = f(&mut session);
+            (session, result)
+        });
+        match tokio::time::timeout(self.timeouts.operation, job).await {
+            Ok(Ok((session, result))) => {
+                self.inner = Some(session);
+                result
+            }
+            Ok(Err(e)) => Err(io_error(io::ErrorKind::Other, format!("IMAP 작업 실패: {}", e))),
+            Err(_) => Err(io_error(
+                io::ErrorKind::TimedOut,
+                format!("IMAP 작업 타임아웃 ({}초)", self.timeouts.operation.as_secs()),
+            )),
+        }
+    }
+
+    /// 세션이 아직 살아 있는지 (타임아웃/패닉으로 버려지지 않았는지)
+    pub fn is_connected(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// 메일함의 UNSEEN 메일을 하나씩 가져와 `handle` 에 넘김
+    ///
+    /// - `accept(uid)` 가 false 인 메일은 가져오지 않음 (다른 샤드 담당 등)
+    /// - 타임아웃은 메일 하나 단위로 적용되어 큰 메일함도 중간에 통째로 버려지지 않음
+    /// - `handle` 이 true 를 돌려준 메일(저장까지 끝난 메일)만 \Seen 표시하므로,
+    ///   도중에 끊겨도 처리하지 못한 메일은 UNSEEN 으로 남아 다음 확인에서 다시 가져옴
+    ///
+    /// 반환값은 `handle` 에 넘긴 메일 수입니다.
+    pub async fn for_each_unseen<A, F, Fut>(
+        &mut self,
+        mailbox: &str,
+        gmail_raw: Option<&str>,
+        accept: A,
+        mut handle: F,
+    ) -> imap::error::Result<usize>
+    where
+        A: Fn(u32) -> bool,
+        F: FnMut(ParsedEmail) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let (mb, raw) = (mailbox.to_string(), gmail_raw.map(str::to_string));
+        let found = self.run(move |s| search_unseen(s, &mb, raw.as_deref())).await?;
+        let mut handled = 0;
+        for uid in found.uids.into_iter().filter(|&uid| accept(uid)) {
+            let (mb, gmail_ext) = (mailbox.to_string(), found.gmail_ext);
+            let email = match self.run(move |s| fetch_unseen_uid(s, &mb, uid, gmail_ext)).await {
+                Ok(Some(email)) => email,
+                Ok(None) => {
+                    warn!("[Gmail] UID {} 본문을 가져오지 못함 — 건너뜀", uid);
+                    continue;
+                }
+                // 세션이 버려졌으면 중단, 아니면 개별 오류로 보고 다음 메일 진행
+                Err(e) if !self.is_connected() => return Err(e),
+                Err(e) => {
+                    warn!("[Gmail] UID {} fetch 실패: {}", uid, e);
+                    continue;
+                }
+            };
+            handled += 1;
+            if handle(email).await {
+                if let Err(e) = self.run(move |s| mark_seen(s, uid)).await {
+                    warn!("[Gmail] UID {} 읽음 표시 실패 (다음 확인에서 다시 가져옴): {}", uid, e);
+                    if !self.is_connected() {
+                        return Err(e);
                     }
+                }
+            }
+        }
+        Ok(handled)
+    }
+
+    /// 로그아웃 (실패는 무시)
+    pub async fn logout(mut self) {
+        let _ = self.run(|s| s.logout()).await;
+    }
+}
+
+/// UNSEEN 검색 결과
+pub struct UnseenSearch {
+    pub uids: Vec<u32>,
+    /// X-GM-EXT-1 지원 여부 (X-GM-MSGID / X-GM-THRID 조회 가능)
+    pub gmail_ext: bool,
+}
+
+/// 메일함 + Gmail 검색식(X-GM-RAW)으로 UNSEEN 메일 UID 검색
+///
+/// `gmail_raw` 예: `category:promotions`, `-category:social label:invoice`.
+/// Gmail 확장이 없는 서버에서는 검색식을 무시하고 경고만 남깁니다.
+pub fn search_unseen(
+    session: &mut GmailSession,
+    mailbox: &str,
+    gmail_raw: Option<&str>,
+) -> imap::error::Result<UnseenSearch> {
+    session.select(mailbox)?;
+    let gmail_ext = supports_gmail_ext(session);
+    let query = match gmail_raw.filter(|r| !r.trim().is_empty()) {
+        Some(raw) if gmail_ext => format!("UNSEEN X-GM-RAW {}", quote_imap(raw.trim())),
+        Some(raw) => {
+            warn!("[Gmail] {} : X-GM-RAW 미지원 서버 — 검색식 '{}' 무시", mailbox, raw);
+            "UNSEEN".to_string()
+        }
+        None => "UNSEEN".to_string(),
+    };
+    // 라벨 적용(UID STORE)과 맞추기 위해 시퀀스 번호 대신 UID 사용
+    let mut uids: Vec<u32> = session.uid_search(&query)?.into_iter().collect();
+    uids.sort_unstable();
+    Ok(UnseenSearch { uids, gmail_ext })
+}
+
+/// 검색된 UNSEEN 메일 하나를 가져와 파싱 (읽음 표시는 하지 않음 — `mark_seen` 으로 따로)
+///
+/// 메일함은 `search_unseen` 으로 이미 선택되어 있어야 합니다.
+pub fn fetch_unseen_uid(
+    session: &mut GmailSession,
+    mailbox: &str,
+    uid: u32,
+    gmail_ext: bool,
+) -> imap::error::Result<Option<ParsedEmail>> {
+    let (gm_msgid, gm_thrid) = if gmail_ext {
+        fetch_gmail_ids(session, uid)
+    } else {
+        (None, None)
+    };
+    let Some(mut email) = fetch_single_email(session, uid)? else { return Ok(None) };
+    email.gm_msgid = gm_msgid;
+    email.gm_thrid = gm_thrid;
+    email.gmail_link = build_gmail_link(email.message_id.as_deref(), gm_msgid);
+    email.mailbox = mailbox.to_string();
+    if !email.parse_warnings.is_empty() {
+        warn!("[Gmail] UID {} 파싱 경고: {:?}", uid, email.parse_warnings);
+    }
+    Ok(Some(email))
+}
+
+/// 처리가 끝난 메일을 읽음 표시
+pub fn mark_seen(session: &mut GmailSession, uid: u32) -> imap::error::Result<()> {
+    session.uid_store(uid.to_string(), "+FLAGS (\\Seen)")?;
+    Ok(())
+}
+
+/// 메일 하나를 가져와 파싱 (읽음 플래그는 건드리지 않음)
+///
+/// 1) 크기가 상한 이하면 BODY.PEEK[] 전체
+/// 2) 실패 시 BODY.PEEK[HEADER] + BODY.PEEK[TEXT]
+/// 3) 너무 크거나 2)도 실패하면 BODYSTRUCTURE 로 본문 파트만 부분 fetch
+pub fn fetch_single_email(
+    session: &mut GmailSession,
+    uid: u32,
+) -> imap::error::Result<Option<ParsedEmail>> {
+    let max_full = env::var("GMAIL_MAX_FETCH_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_MAX_FULL_FETCH);
+    let size = session
+        .uid_fetch(uid.to_string(), "RFC822.SIZE")?
+        .iter()
+        .next()
+        .and_then(|f| f.size);
+
+    let too_large = size.map_or(false, |s| s > max_full);
+    if !too_large {
+        match session.uid_fetch(uid.to_string(), "BODY.PEEK[]") {
+            Ok(fetches) => {
+                if let Some(bytes) = fetches.iter().next().and_then(|f| f.body()) {
+                    return Ok(Some(parse_single_email(uid, bytes)));
+                }
+                warn!("[Gmail] UID {} BODY.PEEK[] 응답에 본문 없음", uid);
+            }
+            Err(e) => warn!("[Gmail] UID {} BODY.PEEK[] 실패: {}", uid, e),
+        }
+        if let Some(email) = try_alternative_fetch(session, uid)? {
+            return Ok(Some(email));
+        }
+    } else {
+        info!("[Gmail] UID {} 크기 {}B > {}B — 부분 fetch 사용", uid, size.unwrap_or(0), max_full);
+    }
+
+    try_partial_fetch(session, uid)
+}
+
+/// 헤더와 본문(TEXT)을 따로 받아 원문을 재구성
+fn try_alternative_fetch(
+    session: &mut GmailSession,
+    uid: u32,
+) -> imap::error::Result<Option<ParsedEmail>> {
+    let fetches = match session.uid_fetch(uid.to_string(), "(BODY.PEEK[HEADER] BODY.PEEK[TEXT])") {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("[Gmail] UID {} HEADER/TEXT fetch 실패: {}", uid, e);
+            return Ok(None);
+        }
+    };
+    let Some(fetch) = fetches.iter().next() else { return Ok(None) };
+    let Some(header) = fetch.header() else { return Ok(None) };
+
+    let mut raw = header.to_vec();
+    if !raw.ends_with(b"\r\n\r\n") && !raw.ends_with(b"\n\n") {
+        raw.extend_from_slice(b"\r\n");
+    }
+    let mut email = match fetch.text() {
+        Some(text) => {
+            raw.extend_from_slice(text);
+            parse_single_email(uid, &raw)
+        }
+        None => {
+            let mut email = parse_single_email(uid, &raw);
+            email.parse_warnings.push("BODY[TEXT] 응답 없음 — 헤더만 파싱".to_string());
+            email
+        }
+    };
+    email.fetch_strategy = FetchStrategy::HeaderAndText;
+    debug!("[Gmail] UID {} header+text fetch 성공", uid);
+    Ok(Some(email))
+}
+
+/// BODYSTRUCTURE 로 본문 파트를 찾아 앞부분만 가져옴 (대용량 메일용)
+fn try_partial_fetch(
+    session: &mut GmailSession,
+    uid: u32,
+) -> imap::error::Result<Option<ParsedEmail>> {
+    let fetches = session.uid_fetch(uid.to_string(), "(BODY.PEEK[HEADER] BODYSTRUCTURE)")?;
+    let Some(fetch) = fetches.iter().next() else { return Ok(None) };
+    let Some(header) = fetch.header() else { return Ok(None) };
+
+    // 헤더만으로 기본 필드 파싱 (원문 일부이므로 보관소에는 넣지 않음)
+    let mut email = parse_message(uid, header);
+    email.fetch_strategy = FetchStrategy::Partial;
+    email.body = "(본문 없음)".into();
+
+    let Some(target) = fetch.bodystructure().and_then(|bs| find_text_part(bs, &mut Vec::new()))
+    else {
+        email.parse_warnings.push("BODYSTRUCTURE 에 텍스트 파트 없음".to_string());
+        return Ok(Some(email));
+    };
+
+    let section = target
+        .path
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    let query = format!("BODY.PEEK[{}]<0.{}>", section, PARTIAL_BODY_BYTES);
+    let part_fetches = session.uid_fetch(uid.to_string(), &query)?;
+    let path = SectionPath::Part(target.path.clone(), None);
+    let data = part_fetches.iter().next().and_then(|f| f.section(&path));
+
+    match data {
+        Some(bytes) => {
+            let decoded_bytes = match target.encoding {
+                PartEncoding::Base64 => decode_base64_lenient(bytes),
+                PartEncoding::QuotedPrintable => decode_quoted_printable(bytes),
+                PartEncoding::Plain => bytes.to_vec(),
+            };
+            let d = decode_bytes(&decoded_bytes, target.charset.as_deref());
+            email.parse_warnings.extend(d.warnings);
+            if target.is_html {
+                let rendered = html_to_text(&d.text);
+                email.body = rendered.text;
+                email.links = rendered.links;
+            } else {
+                email.body = d.text;
+            }
+            if target.octets > PARTIAL_BODY_BYTES {
+                email.parse_warnings.push(format!(
+                    "본문 {}B 중 앞 {}B 만 가져옴",
+                    target.octets, PARTIAL_BODY_BYTES
+                ));
+            }
+        }
+        None => email
+            .parse_warnings
+            .push(format!("BODY[{}] 응답 없음", section)),
+    }
+    debug!("[Gmail] UID {} partial fetch 성공 (section={})", uid, section);
+    Ok(Some(email))
+}
+
+enum PartEncoding {
+    Plain,
+    Base64,
+    QuotedPrintable,
+}
+
+/// BODYSTRUCTURE 에서 찾은 본문 파트 정보
+struct TextPart {
+    path: Vec<u32>,
+    charset: Option<String>,
+    encoding: PartEncoding,
+    is_html: bool,
+    octets: u32,
+}
 
-                    let body = extract_plain_body(&parsed)
-                        .or_else(|| parsed.get_body().ok())
-                        .unwrap_or_else(|| "(본문 없음)".into());
-
-                    // attachments 수집
-                    let mut attachments = Vec::new();
-                    collect_attachments(&parsed, &mut attachments);
-
-                    let gmail_link = format!(
-                        "https://mail.google.com/mail/u/0/#search/rfc822msgid:{}",
-                        uid
-                    );
-
-                    out.push(ParsedEmail {
-                        uid: uid.to_string(),
-                        subject,
-                        from,
-                        body,
-                        attachments,
-                        gmail_link,
-                    });
+/// text/plain 을 우선, 없으면 text/html 파트를 찾음 (첨부 제외)
+fn find_text_part(bs: &BodyStructure, path: &mut Vec<u32>) -> Option<TextPart> {
+    match bs {
+        BodyStructure::Multipart { bodies, .. } => {
+            let mut html = None;
+            for (i, body) in bodies.iter().enumerate() {
+                path.push(i as u32 + 1);
+                if let Some(part) = find_text_part(body, path) {
+                    if !part.is_html {
+                        path.pop();
+                        return Some(part);
+                    }
+                    html.get_or_insert(part);
                 }
-                let _ = session.store(uid.to_string(), "+FLAGS (\\Seen)");
+                path.pop();
+            }
+            html
+        }
+        BodyStructure::Text { common, other, .. } => {
+            let is_attachment = common
+                .disposition
+                .as_ref()
+                .map_or(false, |d| d.ty.eq_ignore_ascii_case("attachment"));
+            let subtype = common.ty.subtype.to_ascii_lowercase();
+            if is_attachment || (subtype != "plain" && subtype != "html") {
+                return None;
             }
+            let charset = common.ty.params.as_ref().and_then(|params| {
+                params
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("charset"))
+                    .map(|(_, v)| v.to_string())
+            });
+            let encoding = match &other.transfer_encoding {
+                ContentEncoding::Base64 => PartEncoding::Base64,
+                ContentEncoding::QuotedPrintable => PartEncoding::QuotedPrintable,
+                _ => PartEncoding::Plain,
+            };
+            Some(TextPart {
+                // 단일 파트 메일은 섹션 1
+                path: if path.is_empty() { vec![1] } else { path.clone() },
+                charset,
+                encoding,
+                is_html: subtype == "html",
+                octets: other.octets,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// RFC822 원문을 ParsedEmail 로 변환하고 원문 보관소에 저장
+///
+/// 파싱에 실패하더라도 메일을 버리지 않고, 가능한 만큼 복구한 뒤
+/// 경고를 `parse_warnings` 에 남깁니다.
+pub fn parse_single_email(uid: u32, bytes: &[u8]) -> ParsedEmail {
+    let mut email = parse_message(uid, bytes);
+    email.raw_sha256 = store_raw(bytes);
+    email
+}
+
+/// 보관소 저장 없이 파싱만 (부분 원문, 보관된 원문 재파싱용)
+pub fn parse_message(uid: u32, bytes: &[u8]) -> ParsedEmail {
+    let mut warnings = Vec::new();
+
+    let parsed = match parse_mail(bytes) {
+        Ok(p) => p,
+        Err(e) => {
+            warnings.push(format!("MIME 파싱 실패: {}", e));
+            return parse_raw_fallback(uid, bytes, warnings);
+        }
+    };
+
+    let header_text = |name: &str, warnings: &mut Vec<String>| {
+        parsed.headers.get_first_header(name).map(|h| {
+            let d = decode_header(h.get_value_raw());
+            warnings.extend(d.warnings.into_iter().map(|w| format!("{}: {}", name, w)));
+            d.text
+        })
+    };
+    let subject = header_text("Subject", &mut warnings)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "(제목 없음)".into());
+    let from = header_text("From", &mut warnings)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "(보낸 사람 없음)".into());
+
+    let (body, links) = extract_plain_body(&parsed, &mut warnings)
+        .or_else(|| {
+            let d = decode_part_body(&parsed);
+            warnings.extend(d.warnings);
+            Some((d.text, Vec::new())).filter(|(t, _)| !t.trim().is_empty())
+        })
+        .unwrap_or_else(|| ("(본문 없음)".into(), Vec::new()));
+
+    // attachments 수집
+    let mut attachments = Vec::new();
+    collect_attachments(&parsed, &mut attachments, &mut warnings);
+
+    let headers = EmailHeaders::from_mail_headers(&parsed.headers);
+    let message_id = headers.message_id.clone();
+    let gmail_link = build_gmail_link(message_id.as_deref(), None);
+
+    ParsedEmail {
+        uid: uid.to_string(),
+        mailbox: String::new(),
+        subject,
+        from,
+        body,
+        links,
+        attachments,
+        gmail_link,
+        message_id,
+        gm_msgid: None,
+        gm_thrid: None,
+        headers,
+        parse_warnings: warnings,
+        fetch_strategy: FetchStrategy::Full,
+        raw_sha256: None,
+    }
+}
+
+/// MIME 구조가 깨진 메일: 헤더/본문을 빈 줄 기준으로 나눠 최대한 복구
+fn parse_raw_fallback(uid: u32, bytes: &[u8], mut warnings: Vec<String>) -> ParsedEmail {
+    let split = bytes
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|p| (p, p + 4))
+        .or_else(|| bytes.windows(2).position(|w| w == b"\n\n").map(|p| (p, p + 2)));
+    let (head, body) = match split {
+        Some((h, b)) => (&bytes[..h], &bytes[b..]),
+        None => (&bytes[..0], bytes),
+    };
+
+    let mut subject = None;
+    let mut from = None;
+    // 접힌 헤더 줄을 먼저 펴야 이어지는 줄이 버려지지 않음
+    let head = unfold(head);
+    for line in head.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let Some(colon) = line.iter().position(|&b| b == b':') else { continue };
+        let (key, value) = (&line[..colon], &line[colon + 1..]);
+        if key.eq_ignore_ascii_case(b"Subject") {
+            subject = Some(decode_header(value).text);
+        } else if key.eq_ignore_ascii_case(b"From") {
+            from = Some(decode_header(value).text);
         }
     }
 
-    Ok(out)
+    let decoded = decode_bytes(body, None);
+    warnings.extend(decoded.warnings);
+
+    ParsedEmail {
+        uid: uid.to_string(),
+        mailbox: String::new(),
+        subject: subject.unwrap_or_else(|| "(제목 없음)".into()),
+        from: from.unwrap_or_else(|| "(보낸 사람 없음)".into()),
+        body: decoded.text,
+        links: Vec::new(),
+        attachments: Vec::new(),
+        gmail_link: build_gmail_link(None, None),
+        message_id: None,
+        gm_msgid: None,
+        gm_thrid: None,
+        headers: EmailHeaders::default(),
+        parse_warnings: warnings,
+        fetch_strategy: FetchStrategy::Full,
+        raw_sha256: None,
+    }
+}
+
+/// 본문(text/plain 우선, 없으면 text/html)과 링크 목록을 추출하는 헬퍼
+fn extract_plain_body(
+    part: &ParsedMail,
+    warnings: &mut Vec<String>,
+) -> Option<(String, Vec<String>)> {
+    if let Some(plain) = find_body_part(part, "text/plain") {
+        let d = decode_part_body(plain);
+        warnings.extend(d.warnings);
+        return Some((d.text, Vec::new()));
+    }
+    let html = find_body_part(part, "text/html")?;
+    let d = decode_part_body(html);
+    warnings.extend(d.warnings);
+    let rendered = html_to_text(&d.text);
+    Some((rendered.text, rendered.links))
+}
+
+/// 주어진 MIME 타입의 첫 번째 본문 파트 (첨부파일 제외)
+fn find_body_part<'a>(part: &'a ParsedMail<'a>, mimetype: &str) -> Option<&'a ParsedMail<'a>> {
+    if part.subparts.is_empty() {
+        let is_attachment = part.get_content_disposition().disposition == DispositionType::Attachment;
+        return (!is_attachment && part.ctype.mimetype.eq_ignore_ascii_case(mimetype)).then_some(part);
+    }
+    part.subparts.iter().find_map(|sub| find_body_part(sub, mimetype))
+}
+
+/// X-GM-MSGID / X-GM-THRID 조회 (Gmail 확장 전용)
+///
+/// 응답 예: `* 3 FETCH (X-GM-MSGID 1278455344230334865 X-GM-THRID 1278455344230334865 UID 12)`
+pub fn fetch_gmail_ids(
+    session: &mut GmailSession,
+    uid: u32,
+) -> (Option<u64>, Option<u64>) {
+    let raw = match session.run_command_and_read_response(format!(
+        "UID FETCH {} (X-GM-MSGID X-GM-THRID)",
+        uid
+    )) {
+        Ok(r) => String::from_utf8_lossy(&r).into_owned(),
+        Err(_) => return (None, None),
+    };
+    let grab = |re: &Regex| re.captures(&raw)?.get(1)?.as_str().parse::<u64>().ok();
+    (grab(&GM_MSGID), grab(&GM_THRID))
 }
 
-// --- 여기에 파일 끝부분에 추가하세요 ---
-fn collect_attachments(part: &ParsedMail, out: &mut Vec<String>) {
-    // 1) Content-Disposition 검사
-    if let Some(disp) = part.get_headers().get_first_value("Content-Disposition") {
-        if disp.to_lowercase().starts_with("attachment") {
-            if let Some(fname) = part.get_content_disposition().params.get("filename") {
-                out.push(fname.clone());
+/// Gmail 웹 딥링크 생성
+///
+/// X-GM-MSGID 가 있으면 16진수 메시지 ID로 바로 열고,
+/// 없으면 Message-ID 검색(rfc822msgid:)으로 대체합니다.
+pub fn build_gmail_link(message_id: Option<&str>, gm_msgid: Option<u64>) -> String {
+    match (gm_msgid, message_id) {
+        (Some(id), _) => format!("https://mail.google.com/mail/u/0/#all/{:x}", id),
+        (None, Some(mid)) => format!(
+            "https://mail.google.com/mail/u/0/#search/rfc822msgid%3A{}",
+            percent_encode(mid)
+        ),
+        (None, None) => "https://mail.google.com/mail/u/0/#inbox".to_string(),
+    }
+}
+
+/// URL fragment 에 넣을 수 있도록 예약 문자 인코딩
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'@' => {
+                out.push(b as char)
             }
+            _ => out.push_str(&format!("%{:02X}", b)),
         }
     }
-    // 2) Content-Type name 파라미터
-    if let Some(name) = part.ctype.params.get("name") {
-        out.push(name.clone());
+    out
+}
+
+/// 첨부파일 수집: 파일명이 있거나 attachment 로 지정된 리프 파트만 한 번씩 디코딩
+fn collect_attachments(part: &ParsedMail, out: &mut Vec<Attachment>, warnings: &mut Vec<String>) {
+    if part.subparts.is_empty() {
+        let disp = part.get_content_disposition();
+        // 1) Content-Disposition filename, 2) Content-Type name 파라미터
+        let name = disp
+            .params
+            .get("filename")
+            .or_else(|| part.ctype.params.get("name"))
+            .map(|n| decode_header(n.as_bytes()).text);
+        if name.is_none() && disp.disposition != DispositionType::Attachment {
+            return;
+        }
+        let name = name.unwrap_or_else(|| "(이름 없음)".to_string());
+        match part.get_body_raw() {
+            Ok(bytes) => {
+                let att = Attachment::from_bytes(
+                    &name,
+                    &part.ctype.mimetype,
+                    &bytes,
+                    Some(&part.ctype.charset),
+                );
+                if let Some(err) = &att.error {
+                    warnings.push(format!("첨부 {}: {}", name, err));
+                }
+                out.push(att);
+            }
+            Err(e) => warnings.push(format!("첨부 {} 디코딩 실패: {}", name, e)),
+        }
+        return;
     }
     // 재귀
     for sub in &part.subparts {
-        collect_attachments(sub, out);
+        collect_attachments(sub, out, warnings);
+    }
+}
+
+/// 분류 카테고리 → 라벨 매핑 설정
+#[derive(Clone, Debug)]
+pub struct LabelConfig {
+    /// 라벨 접두어 (예: "AI" → "AI/Spam")
+    pub prefix: String,
+    /// 카테고리(대문자) → 라벨 이름. 없으면 카테고리를 그대로 사용
+    pub mapping: HashMap<String, String>,
+    /// true면 실제 변경 없이 적용할 내용만 로그로 남김
+    pub dry_run: bool,
+}
+
+impl LabelConfig {
+    /// 환경 변수에서 읽기
+    /// - GMAIL_LABEL_PREFIX  (기본값: AI)
+    /// - GMAIL_LABEL_MAP     (예: "SPAM=Spam,INVOICE=Invoice")
+    /// - GMAIL_LABEL_DRY_RUN (true/1 이면 dry-run)
+    pub fn from_env() -> Self {
+        let prefix = env::var("GMAIL_LABEL_PREFIX").unwrap_or_else(|_| "AI".to_string());
+        let mapping = env::var("GMAIL_LABEL_MAP")
+            .map(|s| parse_label_map(&s))
+            .unwrap_or_default();
+        let dry_run = env::var("GMAIL_LABEL_DRY_RUN")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        LabelConfig { prefix, mapping, dry_run }
+    }
+
+    /// 카테고리에 대응하는 전체 라벨 이름 (예: "SPAM" → "AI/Spam")
+    pub fn label_for(&self, category: &str) -> String {
+        let key = category.trim().to_uppercase();
+        let name = self
+            .mapping
+            .get(&key)
+            .cloned()
+            .unwrap_or_else(|| title_case(category.trim()));
+        if self.prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+}
+
+/// "SPAM=Spam,INVOICE=Invoice" 형식 파싱
+fn parse_label_map(raw: &str) -> HashMap<String, String> {
+    raw.split(',')
+        .filter_map(|pair| {
+            let (k, v) = pair.split_once('=')?;
+            let (k, v) = (k.trim(), v.trim());
+            if k.is_empty() || v.is_empty() {
+                return None;
+            }
+            Some((k.to_uppercase(), v.to_string()))
+        })
+        .collect()
+}
+
+/// "SPAM" → "Spam", "긴급" → "긴급"
+pub fn title_case(s: &str) -> String {
+    let lower = s.to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => "Uncategorized".to_string(),
+    }
+}
+
+/// IMAP quoted string 으로 감싸기
+pub fn quote_imap(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// 메일함/라벨 이름을 modified UTF-7 (RFC 3501 §5.1.3) 로 인코딩 (예: "AI/긴급" → "AI/&rjSuCQ-")
+///
+/// 출력 가능한 ASCII 는 그대로 두고("&" 만 "&-"), 나머지 문자열 구간은
+/// UTF-16BE 를 ',' 를 쓰는 패딩 없는 base64 로 바꿔 "&...-" 로 감쌉니다.
+pub fn encode_mailbox_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut pending: Vec<u16> = Vec::new();
+    let flush = |pending: &mut Vec<u16>, out: &mut String| {
+        if pending.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = pending.iter().flat_map(|u| u.to_be_bytes()).collect();
+        out.push('&');
+        out.push_str(&MUTF7.encode(bytes));
+        out.push('-');
+        pending.clear();
+    };
+    for c in name.chars() {
+        if (' '..='~').contains(&c) {
+            flush(&mut pending, &mut out);
+            out.push(c);
+            if c == '&' {
+                out.push('-');
+            }
+        } else {
+            let mut buf = [0u16; 2];
+            pending.extend_from_slice(c.encode_utf16(&mut buf));
+        }
+    }
+    flush(&mut pending, &mut out);
+    out
+}
+
+/// 라벨 이름을 IMAP 키워드(atom)로 변환 (예: "AI/Spam" → "AI_Spam")
+fn keyword_for(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// 서버가 Gmail IMAP 확장(X-GM-EXT-1)을 지원하는지 확인
+pub fn supports_gmail_ext(session: &mut GmailSession) -> bool {
+    session
+        .capabilities()
+        .map(|caps| caps.has_str("X-GM-EXT-1"))
+        .unwrap_or(false)
+}
+
+/// 라벨(폴더)이 없으면 생성 (서버에는 modified UTF-7 로 인코딩한 이름을 보냄)
+pub fn ensure_label(
+    session: &mut GmailSession,
+    label: &str,
+    dry_run: bool,
+) -> imap::error::Result<()> {
+    let mailbox = encode_mailbox_name(label);
+    let exists = session
+        .list(Some(""), Some(&mailbox))?
+        .iter()
+        .any(|name| name.name() == mailbox);
+    if exists {
+        return Ok(());
+    }
+    if dry_run {
+        info!("[Label] (dry-run) 라벨 생성 예정: {}", label);
+        return Ok(());
+    }
+    session.create(&mailbox)?;
+    info!("[Label] 라벨 생성: {}", label);
+    Ok(())
+}
+
+/// 분류 결과 카테고리에 매핑된 라벨을 메일(UID)에 적용
+///
+/// Gmail 이면 X-GM-LABELS, 그 외 서버는 같은 이름의 폴더로 복사하고 키워드 플래그를 붙입니다.
+/// 현재 선택된 메일함 기준으로 동작하므로 호출 전에 `select` 가 필요합니다.
+pub fn apply_category_label(
+    session: &mut GmailSession,
+    uid: &str,
+    category: &str,
+    config: &LabelConfig,
+) -> imap::error::Result<String> {
+    let label = config.label_for(category);
+    ensure_label(session, &label, config.dry_run)?;
+    let mailbox = encode_mailbox_name(&label);
+
+    if supports_gmail_ext(session) {
+        let query = format!("+X-GM-LABELS ({})", quote_imap(&mailbox));
+        if config.dry_run {
+            info!("[Label] (dry-run) UID {} STORE {}", uid, query);
+        } else {
+            session.uid_store(uid, &query)?;
+            info!("[Label] UID {} → {}", uid, label);
+        }
+    } else {
+        let keyword = keyword_for(&label);
+        if config.dry_run {
+            info!("[Label] (dry-run) UID {} COPY {} / +FLAGS ({})", uid, label, keyword);
+        } else {
+            session.uid_copy(uid, &mailbox)?;
+            session.uid_store(uid, format!("+FLAGS ({})", keyword))?;
+            info!("[Label] UID {} → 폴더 {} / 키워드 {}", uid, label, keyword);
+        }
     }
+    Ok(label)
 }
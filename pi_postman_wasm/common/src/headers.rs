@@ -0,0 +1,205 @@
+// common/src/headers.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "native")]
+use chrono::TimeZone;
+#[cfg(feature = "native")]
+use crate::mime::decode_header;
+#[cfg(feature = "native")]
+use mailparse::{addrparse_header, dateparse, MailAddr, MailHeader, MailHeaderMap};
+
+/// 표시 이름이 포함된 메일 주소
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MailAddress {
+    pub name: Option<String>,
+    pub address: String,
+}
+
+impl std::fmt::Display for MailAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} <{}>", name, self.address),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+/// 파싱된 메일 헤더 모델
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EmailHeaders {
+    pub from: Option<MailAddress>,
+    pub to: Vec<MailAddress>,
+    pub cc: Vec<MailAddress>,
+    pub reply_to: Vec<MailAddress>,
+    pub date: Option<DateTime<Utc>>,
+    pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    pub references: Vec<String>,
+    pub list_id: Option<String>,
+    /// List-Unsubscribe 의 각 URI (mailto:, https:)
+    pub list_unsubscribe: Vec<String>,
+    /// Authentication-Results 헤더 (여러 개 가능)
+    pub authentication_results: Vec<String>,
+    pub x_mailer: Option<String>,
+    /// 원본 헤더 (이름, 디코딩된 값) — 순서 유지
+    pub raw: Vec<(String, String)>,
+}
+
+impl EmailHeaders {
+    /// 이름이 일치하는 첫 번째 원본 헤더 값 (대소문자 무시)
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.raw
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 이름이 일치하는 모든 원본 헤더 값
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.raw
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 메일링 리스트 발송 여부
+    pub fn is_mailing_list(&self) -> bool {
+        self.list_id.is_some() || !self.list_unsubscribe.is_empty()
+    }
+
+    /// 분류 프롬프트에 넣을 헤더 요약 (값이 있는 항목만)
+    pub fn summary(&self) -> String {
+        let join = |v: &[MailAddress]| {
+            v.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+        };
+        let mut lines = Vec::new();
+        if let Some(from) = &self.from {
+            lines.push(format!("보낸이: {}", from));
+        }
+        if !self.to.is_empty() {
+            lines.push(format!("받는이: {}", join(&self.to)));
+        }
+        if !self.cc.is_empty() {
+            lines.push(format!("참조: {}", join(&self.cc)));
+        }
+        if !self.reply_to.is_empty() {
+            lines.push(format!("회신 주소: {}", join(&self.reply_to)));
+        }
+        if let Some(date) = &self.date {
+            lines.push(format!("날짜: {}", date.to_rfc3339()));
+        }
+        if let Some(list_id) = &self.list_id {
+            lines.push(format!("List-Id: {}", list_id));
+        }
+        if self.in_reply_to.is_some() {
+            lines.push("답장 메일: 예".to_string());
+        }
+        for auth in &self.authentication_results {
+            lines.push(format!("Authentication-Results: {}", auth));
+        }
+        if let Some(mailer) = &self.x_mailer {
+            lines.push(format!("X-Mailer: {}", mailer));
+        }
+        lines.join("\n")
+    }
+}
+
+/// "<a@b> <c@d>" → ["a@b", "c@d"]
+pub fn parse_msg_id_list(raw: &str) -> Vec<String> {
+    let ids: Vec<String> = raw
+        .split('<')
+        .filter_map(|part| part.split('>').next())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if ids.is_empty() && !raw.trim().is_empty() {
+        // 꺾쇠 없이 공백으로만 구분된 경우
+        return raw.split_whitespace().map(|s| s.to_string()).collect();
+    }
+    ids
+}
+
+#[cfg(feature = "native")]
+impl EmailHeaders {
+    /// mailparse 헤더 목록에서 모델 생성
+    pub fn from_mail_headers(headers: &[MailHeader]) -> Self {
+        let first = |name: &str| {
+            headers
+                .get_first_header(name)
+                .map(|h| decode_header(h.get_value_raw()).text)
+        };
+        let addrs = |name: &str| -> Vec<MailAddress> {
+            headers
+                .get_all_headers(name)
+                .into_iter()
+                .flat_map(|h| parse_addresses(h))
+                .collect()
+        };
+
+        let date = first("Date")
+            .and_then(|d| dateparse(&d).ok())
+            .and_then(|ts| Utc.timestamp_opt(ts, 0).single());
+
+        EmailHeaders {
+            from: addrs("From").into_iter().next(),
+            to: addrs("To"),
+            cc: addrs("Cc"),
+            reply_to: addrs("Reply-To"),
+            date,
+            message_id: first("Message-ID")
+                .and_then(|v| parse_msg_id_list(&v).into_iter().next()),
+            in_reply_to: first("In-Reply-To")
+                .and_then(|v| parse_msg_id_list(&v).into_iter().next()),
+            references: first("References")
+                .map(|v| parse_msg_id_list(&v))
+                .unwrap_or_default(),
+            list_id: first("List-Id"),
+            list_unsubscribe: first("List-Unsubscribe")
+                .map(|v| parse_msg_id_list(&v))
+                .unwrap_or_default(),
+            authentication_results: headers
+                .get_all_headers("Authentication-Results")
+                .into_iter()
+                .map(|h| decode_header(h.get_value_raw()).text)
+                .collect(),
+            x_mailer: first("X-Mailer"),
+            raw: headers
+                .iter()
+                .map(|h| (h.get_key(), decode_header(h.get_value_raw()).text))
+                .collect(),
+        }
+    }
+}
+
+/// 주소 헤더 하나를 파싱 (그룹 주소는 펼침)
+#[cfg(feature = "native")]
+fn parse_addresses(header: &MailHeader) -> Vec<MailAddress> {
+    let list = match addrparse_header(header) {
+        Ok(list) => list,
+        Err(_) => {
+            // 파싱 실패 시 원문을 주소로 보존
+            let raw = header.get_value();
+            return if raw.trim().is_empty() {
+                Vec::new()
+            } else {
+                vec![MailAddress { name: None, address: raw.trim().to_string() }]
+            };
+        }
+    };
+    let mut out = Vec::new();
+    for addr in list.iter() {
+        match addr {
+            MailAddr::Single(info) => out.push(MailAddress {
+                name: info.display_name.clone(),
+                address: info.addr.clone(),
+            }),
+            MailAddr::Group(group) => out.extend(group.addrs.iter().map(|info| MailAddress {
+                name: info.display_name.clone(),
+                address: info.addr.clone(),
+            })),
+        }
+    }
+    out
+}
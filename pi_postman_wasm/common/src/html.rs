@@ -0,0 +1,235 @@
+// common/src/html.rs
+//
+// HTML 메일 본문 → 읽기 좋은 텍스트 변환
+
+use ego_tree::NodeRef;
+use scraper::{Html, Node};
+
+/// 변환 결과: 본문 텍스트와 링크 목록
+///
+/// 본문에는 링크 위치에 `[1]` 같은 번호가 붙고, 실제 주소는 `links[0]` 에 들어갑니다.
+#[derive(Clone, Debug, Default)]
+pub struct RenderedHtml {
+    pub text: String,
+    pub links: Vec<String>,
+}
+
+/// 보이지 않는 요소 (내용 전체 무시)
+const SKIP_TAGS: &[&str] = &[
+    "head", "title", "script", "style", "noscript", "template", "svg", "object", "iframe",
+];
+
+/// 앞뒤로 빈 줄을 넣는 블록 요소
+const PARAGRAPH_TAGS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "table", "ul", "ol", "dl",
+    "hr",
+];
+
+/// 앞뒤로 줄바꿈만 넣는 블록 요소
+const LINE_TAGS: &[&str] = &[
+    "div", "section", "article", "header", "footer", "nav", "aside", "main", "tr", "li", "dt",
+    "dd", "caption", "center", "address", "figure", "form",
+];
+
+/// HTML 을 텍스트로 렌더링
+pub fn html_to_text(html: &str) -> RenderedHtml {
+    let doc = Html::parse_document(html);
+    let mut r = Renderer::default();
+    r.walk(doc.tree.root());
+    RenderedHtml { text: r.out.trim().to_string(), links: r.links }
+}
+
+#[derive(Default)]
+struct Renderer {
+    out: String,
+    links: Vec<String>,
+    /// 다음 글자 전에 넣을 줄바꿈 수 (최대 2)
+    pending_newlines: usize,
+    pending_space: bool,
+    /// 목록 중첩: Some(n) = 순서 있는 목록의 다음 번호
+    lists: Vec<Option<usize>>,
+    pre_depth: usize,
+}
+
+impl Renderer {
+    fn walk(&mut self, node: NodeRef<Node>) {
+        match node.value() {
+            Node::Text(text) => self.write_text(text),
+            Node::Element(el) => {
+                let name = el.name();
+                if SKIP_TAGS.contains(&name) || is_hidden(el) {
+                    return;
+                }
+                match name {
+                    "br" => {
+                        self.line_break(1);
+                        return;
+                    }
+                    "hr" => {
+                        self.line_break(2);
+                        self.write_raw("----");
+                        self.line_break(2);
+                        return;
+                    }
+                    "img" => {
+                        if let Some(alt) = el.attr("alt").map(str::trim).filter(|a| !a.is_empty()) {
+                            self.write_text(&format!("[{}]", alt));
+                        }
+                        return;
+                    }
+                    _ => {}
+                }
+
+                let block = if PARAGRAPH_TAGS.contains(&name) {
+                    2
+                } else if LINE_TAGS.contains(&name) {
+                    1
+                } else {
+                    0
+                };
+                if block > 0 {
+                    self.line_break(block);
+                }
+
+                match name {
+                    "ul" => self.lists.push(None),
+                    "ol" => self.lists.push(Some(1)),
+                    "li" => {
+                        let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                        let marker = match self.lists.last_mut() {
+                            Some(Some(n)) => {
+                                *n += 1;
+                                format!("{}{}. ", indent, *n - 1)
+                            }
+                            _ => format!("{}- ", indent),
+                        };
+                        self.write_raw(&marker);
+                    }
+                    // 행의 첫 칸이 아니면 구분자
+                    "td" | "th"
+                        if self.pending_newlines == 0
+                            && !self.out.is_empty()
+                            && !self.out.ends_with('\n') =>
+                    {
+                        self.write_raw(" | ");
+                    }
+                    "pre" => self.pre_depth += 1,
+                    _ => {}
+                }
+
+                // 대기 중인 줄바꿈이 앞 공백을 잘라 내므로 시작 위치를 잡기 전에 반영
+                if name == "a" {
+                    self.flush();
+                }
+                let link_start = self.out.len();
+                for child in node.children() {
+                    self.walk(child);
+                }
+
+                match name {
+                    "ul" | "ol" => {
+                        self.lists.pop();
+                    }
+                    "pre" => self.pre_depth -= 1,
+                    "a" => {
+                        if let Some(href) = el.attr("href") {
+                            self.add_link(href.trim(), link_start);
+                        }
+                    }
+                    _ => {}
+                }
+
+                if block > 0 {
+                    self.line_break(block);
+                }
+            }
+            _ => {
+                for child in node.children() {
+                    self.walk(child);
+                }
+            }
+        }
+    }
+
+    /// 링크 텍스트 뒤에 번호를 붙이고 주소를 목록에 추가 (중복 제거)
+    fn add_link(&mut self, href: &str, text_start: usize) {
+        let lower = href.to_ascii_lowercase();
+        if href.is_empty() || href.starts_with('#') || lower.starts_with("javascript:") {
+            return;
+        }
+        // 링크 텍스트가 주소 자체면 번호 생략
+        if self.out.get(text_start..).unwrap_or("").trim() == href {
+            return;
+        }
+        let idx = match self.links.iter().position(|l| l == href) {
+            Some(i) => i,
+            None => {
+                self.links.push(href.to_string());
+                self.links.len() - 1
+            }
+        };
+        self.write_raw(&format!("[{}]", idx + 1));
+    }
+
+    fn line_break(&mut self, n: usize) {
+        self.pending_newlines = self.pending_newlines.max(n).min(2);
+        self.pending_space = false;
+    }
+
+    /// 대기 중인 줄바꿈/공백 반영
+    fn flush(&mut self) {
+        if self.pending_newlines > 0 {
+            if !self.out.is_empty() {
+                let trimmed = self.out.trim_end_matches([' ', '\t']).len();
+                self.out.truncate(trimmed);
+                let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+                for _ in existing..self.pending_newlines {
+                    self.out.push('\n');
+                }
+            }
+            self.pending_newlines = 0;
+            self.pending_space = false;
+        } else if self.pending_space {
+            if !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+                self.out.push(' ');
+            }
+            self.pending_space = false;
+        }
+    }
+
+    fn write_raw(&mut self, s: &str) {
+        self.flush();
+        self.out.push_str(s);
+    }
+
+    /// 텍스트 노드 출력 (pre 밖에서는 공백 압축)
+    fn write_text(&mut self, text: &str) {
+        if self.pre_depth > 0 {
+            self.write_raw(&text.replace('\u{a0}', " "));
+            return;
+        }
+        for c in text.chars() {
+            if c.is_whitespace() || c == '\u{a0}' || c == '\u{200b}' {
+                self.pending_space = true;
+            } else {
+                self.flush();
+                self.out.push(c);
+            }
+        }
+    }
+}
+
+/// hidden 속성, aria-hidden, display:none 스타일
+fn is_hidden(el: &scraper::node::Element) -> bool {
+    if el.attr("hidden").is_some() || el.attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    el.attr("style").is_some_and(|style| {
+        let style: String = style
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        style.contains("display:none") || style.contains("visibility:hidden")
+    })
+}
@@ -0,0 +1,303 @@
+// common/src/jmap.rs
+//
+// JMAP 메일 소스 (RFC 8620 / 8621)
+// Email/query + Email/get 으로 안 읽은 메일을 찾고, 원문 blob 을 받아 파싱합니다.
+// $seen 표시는 저장이 끝난 메일만 `ack` 에서 하므로, 그 전에 실패하면 다음 확인에서 다시 가져옵니다.
+// 새 메일 대기는 EventSource 푸시를 사용하고, 실패하면 주기 폴링으로 대신합니다.
+
+use crate::gmail::{parse_single_email, ParsedEmail};
+use anyhow::{anyhow, Context, Result};
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const USING: [&str; 2] = ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"];
+const MAIL_CAPABILITY: &str = "urn:ietf:params:jmap:mail";
+/// 한 번에 가져올 최대 메일 수
+const MAX_PER_POLL: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct JmapConfig {
+    /// 세션 리소스 URL (예: https://api.fastmail.com/jmap/session)
+    pub session_url: String,
+    pub username: String,
+    /// 비밀번호 또는 API 토큰
+    pub password: String,
+    /// true 면 password 를 Bearer 토큰으로 사용
+    pub bearer: bool,
+    pub timeout: Duration,
+}
+
+/// 세션 응답에서 필요한 부분
+#[derive(Clone, Debug)]
+struct JmapSession {
+    api_url: String,
+    download_url: String,
+    event_source_url: Option<String>,
+    account_id: String,
+}
+
+pub struct JmapSource {
+    config: JmapConfig,
+    client: Client,
+    session: Option<JmapSession>,
+    inbox_id: Option<String>,
+    /// 저장은 끝났지만 $seen 표시에 실패한 메일을 다시 처리하지 않도록 기억
+    processed: HashSet<String>,
+}
+
+impl JmapSource {
+    pub fn new(config: JmapConfig) -> Result<Self> {
+        let client = Client::builder()
+            .connect_timeout(config.timeout)
+            .build()
+            .context("JMAP HTTP 클라이언트 생성 실패")?;
+        Ok(JmapSource { config, client, session: None, inbox_id: None, processed: HashSet::new() })
+    }
+
+    fn auth(&self, req: RequestBuilder) -> RequestBuilder {
+        if self.config.bearer {
+            req.bearer_auth(&self.config.password)
+        } else {
+            req.basic_auth(&self.config.username, Some(&self.config.password))
+        }
+    }
+
+    /// 세션 정보 (처음 한 번만 조회)
+    async fn session(&mut self) -> Result<JmapSession> {
+        if let Some(s) = &self.session {
+            return Ok(s.clone());
+        }
+        let resp: Value = self
+            .auth(self.client.get(&self.config.session_url))
+            .timeout(self.config.timeout)
+            .send()
+            .await
+            .context("JMAP 세션 요청 실패")?
+            .error_for_status()?
+            .json()
+            .await?;
+        let text = |key: &str| resp.get(key).and_then(Value::as_str).map(str::to_string);
+        let session = JmapSession {
+            api_url: text("apiUrl").ok_or_else(|| anyhow!("JMAP 세션에 apiUrl 없음"))?,
+            download_url: text("downloadUrl").ok_or_else(|| anyhow!("JMAP 세션에 downloadUrl 없음"))?,
+            event_source_url: text("eventSourceUrl"),
+            account_id: resp
+                .get("primaryAccounts")
+                .and_then(|a| a.get(MAIL_CAPABILITY))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("JMAP 메일 계정 없음"))?,
+        };
+        debug!("[JMAP] 세션: api={} account={}", session.api_url, session.account_id);
+        self.session = Some(session.clone());
+        Ok(session)
+    }
+
+    /// methodCalls 실행 후 methodResponses 반환
+    async fn call(&mut self, calls: Value) -> Result<Vec<Value>> {
+        let session = self.session().await?;
+        let resp = self
+            .auth(self.client.post(&session.api_url))
+            .timeout(self.config.timeout)
+            .json(&json!({ "using": USING, "methodCalls": calls }))
+            .send()
+            .await
+            .context("JMAP API 요청 실패")?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            // 세션이 만료됐을 수 있으므로 다음 호출에서 다시 조회
+            self.session = None;
+        }
+        let body: Value = resp.error_for_status()?.json().await?;
+        let responses = body
+            .get("methodResponses")
+            .and_then(Value::as_array)
+            .cloned()
+            .ok_or_else(|| anyhow!("JMAP 응답에 methodResponses 없음"))?;
+        for r in &responses {
+            if r.get(0).and_then(Value::as_str) == Some("error") {
+                return Err(anyhow!("JMAP 메서드 오류: {}", r.get(1).unwrap_or(&Value::Null)));
+            }
+        }
+        Ok(responses)
+    }
+
+    async fn inbox_id(&mut self) -> Result<String> {
+        if let Some(id) = &self.inbox_id {
+            return Ok(id.clone());
+        }
+        let account = self.session().await?.account_id;
+        let responses = self
+            .call(json!([["Mailbox/query", { "accountId": account, "filter": { "role": "inbox" } }, "m"]]))
+            .await?;
+        let id = responses
+            .first()
+            .and_then(|r| r.pointer("/1/ids/0"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("JMAP inbox 메일함을 찾을 수 없음"))?;
+        self.inbox_id = Some(id.clone());
+        Ok(id)
+    }
+
+    /// 안 읽은 inbox 메일 가져오기 (읽음 표시는 `ack` 에서)
+    pub async fn fetch_new(&mut self) -> Result<Vec<ParsedEmail>> {
+        let session = self.session().await?;
+        let inbox = self.inbox_id().await?;
+        let account = session.account_id.clone();
+
+        let responses = self
+            .call(json!([
+                ["Email/query", {
+                    "accountId": account,
+                    "filter": { "inMailbox": inbox, "notKeyword": "$seen" },
+                    "sort": [{ "property": "receivedAt", "isAscending": true }],
+                    "limit": MAX_PER_POLL
+                }, "q"],
+                ["Email/get", {
+                    "accountId": account,
+                    "#ids": { "resultOf": "q", "name": "Email/query", "path": "/ids" },
+                    "properties": ["id", "blobId"]
+                }, "g"]
+            ]))
+            .await?;
+        let list = responses
+            .iter()
+            .find(|r| r.get(2).and_then(Value::as_str) == Some("g"))
+            .and_then(|r| r.pointer("/1/list"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut mails = Vec::new();
+        for item in list {
+            let (Some(id), Some(blob)) = (
+                item.get("id").and_then(Value::as_str),
+                item.get("blobId").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            if self.processed.contains(id) {
+                continue;
+            }
+            match self.download(&session, blob).await {
+                Ok(raw) => {
+                    let mut mail = parse_single_email(0, &raw);
+                    mail.uid = id.to_string();
+                    mail.mailbox = "INBOX".to_string();
+                    for w in &mail.parse_warnings {
+                        warn!("[JMAP] {} 파싱 경고: {}", id, w);
+                    }
+                    mails.push(mail);
+                }
+                Err(e) => warn!("[JMAP] {} 원문 다운로드 실패: {}", id, e),
+            }
+        }
+        info!("[JMAP] {} 새 메일 {}건", self.config.username, mails.len());
+        Ok(mails)
+    }
+
+    /// 저장이 끝난 메일을 $seen 표시
+    ///
+    /// 표시가 거부되거나 요청이 실패한 메일은 processed 에 남겨 이 프로세스에서는 다시 처리하지 않습니다.
+    pub async fn ack(&mut self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let account = self.session().await?.account_id;
+        let update: serde_json::Map<String, Value> =
+            ids.iter().map(|id| (id.clone(), json!({ "keywords/$seen": true }))).collect();
+        let responses = match self
+            .call(json!([["Email/set", { "accountId": account, "update": update }, "s"]]))
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                self.processed.extend(ids.iter().cloned());
+                return Err(e.context("JMAP $seen 표시 실패"));
+            }
+        };
+        // 일부만 실패하면 notUpdated 에 사유가 옴
+        let not_updated = responses
+            .iter()
+            .find(|r| r.get(2).and_then(Value::as_str) == Some("s"))
+            .and_then(|r| r.pointer("/1/notUpdated"))
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        for (id, err) in &not_updated {
+            warn!("[JMAP] {} $seen 표시 거부: {}", id, err);
+            self.processed.insert(id.clone());
+        }
+        Ok(())
+    }
+
+    /// downloadUrl 템플릿으로 원문 blob 다운로드
+    async fn download(&self, session: &JmapSession, blob_id: &str) -> Result<Vec<u8>> {
+        let url = session
+            .download_url
+            .replace("{accountId}", &session.account_id)
+            .replace("{blobId}", blob_id)
+            .replace("{type}", "message%2Frfc822")
+            .replace("{name}", "message.eml");
+        let bytes = self
+            .auth(self.client.get(url))
+            .timeout(self.config.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(bytes.to_vec())
+    }
+
+    /// 새 메일 알림 대기 (EventSource 푸시, 최대 `max_wait`)
+    ///
+    /// 푸시를 쓸 수 없으면 `max_wait` 만큼 잠들었다가 돌아옵니다.
+    pub async fn wait_for_change(&mut self, max_wait: Duration) {
+        let url = match self.session().await.map(|s| s.event_source_url) {
+            Ok(Some(url)) => url
+                .replace("{types}", "Email")
+                .replace("{closeafter}", "state")
+                .replace("{ping}", "60"),
+            _ => {
+                tokio::time::sleep(max_wait).await;
+                return;
+            }
+        };
+        let wait = async {
+            let mut resp = self
+                .auth(self.client.get(&url))
+                .header("Accept", "text/event-stream")
+                .send()
+                .await?
+                .error_for_status()?;
+            let mut buf = String::new();
+            while let Some(chunk) = resp.chunk().await? {
+                buf.push_str(&String::from_utf8_lossy(&chunk));
+                if buf.contains("event: state") || buf.contains("\"StateChange\"") {
+                    return Ok::<bool, anyhow::Error>(true);
+                }
+                // ping 만 쌓이지 않도록 마지막 이벤트만 유지
+                if let Some(pos) = buf.rfind("\n\n") {
+                    buf.drain(..pos);
+                }
+            }
+            Ok(false)
+        };
+        match tokio::time::timeout(max_wait, wait).await {
+            Ok(Ok(true)) => debug!("[JMAP] 상태 변경 푸시 수신"),
+            Ok(Ok(false)) => {
+                debug!("[JMAP] EventSource 연결 종료 — 폴링으로 대기");
+                tokio::time::sleep(max_wait).await;
+            }
+            Ok(Err(e)) => {
+                warn!("[JMAP] EventSource 실패 — 폴링으로 대기: {}", e);
+                tokio::time::sleep(max_wait).await;
+            }
+            Err(_) => {}
+        }
+    }
+}
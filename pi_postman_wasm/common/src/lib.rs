@@ -2,8 +2,31 @@
 
 pub mod classifier;
 pub mod email;
+pub mod headers;
+pub mod search;
+pub mod store;
 
+#[cfg(feature = "native")]
+pub mod archive;
+#[cfg(feature = "native")]
+pub mod attachment;
 #[cfg(feature = "native")]
 pub mod discord;
 #[cfg(feature = "native")]
+pub mod events;
+#[cfg(feature = "native")]
 pub mod gmail;
+#[cfg(feature = "native")]
+pub mod html;
+#[cfg(feature = "native")]
+pub mod jmap;
+#[cfg(feature = "native")]
+pub mod local;
+#[cfg(feature = "native")]
+pub mod mime;
+#[cfg(feature = "native")]
+pub mod pop3;
+#[cfg(feature = "native")]
+pub mod source;
+#[cfg(feature = "native")]
+pub mod sqlite;
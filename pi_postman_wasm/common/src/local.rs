@@ -0,0 +1,187 @@
+// common/src/local.rs
+//
+// 로컬 메일 원본(.eml / mbox / Maildir) → ParsedEmail
+// IMAP 없이 보관 메일 재처리, 테스트, 오프라인 데모에 사용
+
+use crate::gmail::{parse_single_email, ParsedEmail};
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// 로컬 원본 형식
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocalFormat {
+    /// 메일 한 통짜리 .eml 파일
+    Eml,
+    /// "From " 줄로 구분된 mbox 파일
+    Mbox,
+    /// cur/ new/ 하위 디렉터리를 가진 Maildir
+    Maildir,
+    /// .eml 파일이 모여 있는 일반 디렉터리
+    EmlDir,
+}
+
+impl LocalFormat {
+    /// 경로를 보고 형식 추정
+    pub fn detect(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            if path.join("cur").is_dir() || path.join("new").is_dir() {
+                return Ok(LocalFormat::Maildir);
+            }
+            return Ok(LocalFormat::EmlDir);
+        }
+        // 구분자만 보면 되므로 앞부분만 읽음 (큰 mbox 를 두 번 읽지 않도록)
+        let mut head = [0u8; 5];
+        let mut file = fs::File::open(path).with_context(|| format!("{} 열기 실패", path.display()))?;
+        let mut len = 0;
+        while len < head.len() {
+            match file.read(&mut head[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e).with_context(|| format!("{} 읽기 실패", path.display())),
+            }
+        }
+        if head[..len].starts_with(b"From ") {
+            Ok(LocalFormat::Mbox)
+        } else {
+            Ok(LocalFormat::Eml)
+        }
+    }
+}
+
+/// 경로 하나에서 모든 메일 읽기 (형식 자동 판별)
+///
+/// UID 는 읽은 순서대로 1부터 매기고, `mailbox` 에는 원본 파일 경로가 들어갑니다.
+pub fn load_path(path: impl AsRef<Path>) -> Result<Vec<ParsedEmail>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(anyhow!("{} 경로가 없습니다", path.display()));
+    }
+    let format = LocalFormat::detect(path)?;
+    debug!("[Local] {} 형식={:?}", path.display(), format);
+
+    let mut mails = Vec::new();
+    match format {
+        LocalFormat::Eml => mails.push(read_eml(path, 1)?),
+        LocalFormat::Mbox => mails = read_mbox(path)?,
+        LocalFormat::Maildir => {
+            for file in maildir_files(path)? {
+                push_file(&mut mails, &file);
+            }
+        }
+        LocalFormat::EmlDir => {
+            for file in sorted_files(path)? {
+                if file.extension().is_some_and(|e| e.eq_ignore_ascii_case("eml")) {
+                    push_file(&mut mails, &file);
+                }
+            }
+        }
+    }
+    Ok(mails)
+}
+
+/// 읽을 수 없는 파일은 경고만 남기고 건너뜀
+fn push_file(mails: &mut Vec<ParsedEmail>, file: &Path) {
+    match read_eml(file, mails.len() as u32 + 1) {
+        Ok(mail) => mails.push(mail),
+        Err(e) => warn!("[Local] {} 건너뜀: {}", file.display(), e),
+    }
+}
+
+/// .eml 파일 하나 읽기
+pub fn read_eml(path: &Path, uid: u32) -> Result<ParsedEmail> {
+    let bytes = fs::read(path).with_context(|| format!("{} 읽기 실패", path.display()))?;
+    let mut mail = parse_single_email(uid, &bytes);
+    mail.mailbox = path.display().to_string();
+    Ok(mail)
+}
+
+/// mbox 파일의 모든 메일 읽기
+pub fn read_mbox(path: &Path) -> Result<Vec<ParsedEmail>> {
+    let bytes = fs::read(path).with_context(|| format!("{} 읽기 실패", path.display()))?;
+    let mailbox = path.display().to_string();
+    Ok(split_mbox(&bytes)
+        .into_iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            let mut mail = parse_single_email(i as u32 + 1, &raw);
+            mail.mailbox = mailbox.clone();
+            mail
+        })
+        .collect())
+}
+
+/// mbox 원문을 메일별 RFC822 바이트로 분리
+///
+/// 줄 첫머리의 "From " 을 구분자로 보고, mboxrd 방식으로 이스케이프된
+/// ">From " 줄은 '>' 하나를 벗겨 냅니다.
+pub fn split_mbox(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        if line.starts_with(b"From ") {
+            if let Some(msg) = current.take() {
+                messages.push(trim_separator(msg));
+            }
+            current = Some(Vec::new());
+            continue;
+        }
+        let Some(msg) = current.as_mut() else {
+            // 첫 구분자 이전의 내용은 무시
+            continue;
+        };
+        let quoted = line.iter().take_while(|&&b| b == b'>').count();
+        if quoted > 0 && line[quoted..].starts_with(b"From ") {
+            msg.extend_from_slice(&line[1..]);
+        } else {
+            msg.extend_from_slice(line);
+        }
+    }
+    if let Some(msg) = current {
+        messages.push(trim_separator(msg));
+    }
+    messages.retain(|m| !m.is_empty());
+    messages
+}
+
+/// 다음 구분자 앞에 붙는 빈 줄 제거
+fn trim_separator(mut msg: Vec<u8>) -> Vec<u8> {
+    if msg.ends_with(b"\r\n\r\n") {
+        msg.truncate(msg.len() - 2);
+    } else if msg.ends_with(b"\n\n") {
+        msg.truncate(msg.len() - 1);
+    }
+    msg
+}
+
+/// Maildir 의 new/ 와 cur/ 메일 파일 (tmp/ 는 쓰는 중이므로 제외)
+fn maildir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for sub in ["new", "cur"] {
+        let path = dir.join(sub);
+        if path.is_dir() {
+            files.extend(sorted_files(&path)?);
+        }
+    }
+    Ok(files)
+}
+
+/// 디렉터리의 일반 파일 목록 (이름순, 숨김 파일 제외)
+fn sorted_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("{} 디렉터리 읽기 실패", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            !p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
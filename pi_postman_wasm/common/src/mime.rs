@@ -0,0 +1,366 @@
+// common/src/mime.rs
+//
+// 레거시 문자셋(EUC-KR/CP949/ISO-2022-KR)과 깨진 RFC 2047 encoded-word 복구
+
+use base64::alphabet::STANDARD;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD_NO_PAD};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use encoding_rs::{Encoding, EUC_KR, UTF_8};
+use lazy_static::lazy_static;
+use mailparse::ParsedMail;
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+lazy_static! {
+    /// =?charset?B|Q?text?=  (charset 뒤 *lang 허용)
+    static ref ENCODED_WORD: Regex =
+        Regex::new(r"=\?([^?\s]+)\?([bBqQ])\?([^?]*)\?=").unwrap();
+}
+
+/// 패딩 유무와 마지막 조각의 남는 비트를 따지지 않는 base64 엔진
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_allow_trailing_bits(true)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// 디코딩 결과와 경고 목록
+pub struct Decoded {
+    pub text: String,
+    pub warnings: Vec<String>,
+}
+
+/// 메일에서 흔히 쓰이는 별칭을 실제 인코딩으로 매핑
+fn lookup_charset(label: &str) -> Option<&'static Encoding> {
+    let label = label.trim().trim_matches('"').to_ascii_lowercase();
+    match label.as_str() {
+        "cp949" | "ms949" | "uhc" | "x-uhc" | "ks_c_5601" | "ks_c_5601-1987" | "ksc5601"
+        | "x-windows-949" | "windows-949" | "korean" | "euc_kr" => Some(EUC_KR),
+        "utf8" => Some(UTF_8),
+        _ => Encoding::for_label(label.as_bytes()),
+    }
+}
+
+fn is_iso_2022_kr(label: &str) -> bool {
+    let label = label.trim().trim_matches('"');
+    label.eq_ignore_ascii_case("iso-2022-kr") || label.eq_ignore_ascii_case("csiso2022kr")
+}
+
+/// ISO-2022-KR → EUC-KR 바이트로 변환 후 디코딩
+///
+/// `ESC $ ) C` 지시자 이후 SO(0x0E)~SI(0x0F) 구간이 KS X 1001 이며,
+/// 각 바이트에 0x80 을 더하면 EUC-KR 과 같아집니다.
+fn decode_iso_2022_kr(bytes: &[u8]) -> (String, bool) {
+    let mut euc = Vec::with_capacity(bytes.len());
+    let mut shifted = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b if bytes[i..].starts_with(b"\x1b$)C") => {
+                i += 4;
+                continue;
+            }
+            0x0e => shifted = true,
+            0x0f => shifted = false,
+            b'\r' | b'\n' => {
+                // 줄바꿈에서는 ASCII 모드로 복귀
+                shifted = false;
+                euc.push(bytes[i]);
+            }
+            b if shifted && (0x21..=0x7e).contains(&b) => euc.push(b | 0x80),
+            b => euc.push(b),
+        }
+        i += 1;
+    }
+    let (text, _, had_errors) = EUC_KR.decode(&euc);
+    (text.into_owned(), had_errors)
+}
+
+/// 바이트를 지정 문자셋으로 디코딩
+///
+/// 문자셋이 없거나(us-ascii 포함) 틀린 경우 UTF-8 → EUC-KR 순으로 추정합니다.
+pub fn decode_bytes(bytes: &[u8], charset: Option<&str>) -> Decoded {
+    let mut warnings = Vec::new();
+    let label = charset.unwrap_or("").trim();
+
+    if is_iso_2022_kr(label) {
+        let (text, had_errors) = decode_iso_2022_kr(bytes);
+        if had_errors {
+            warnings.push("ISO-2022-KR 디코딩 중 잘못된 바이트 대체".to_string());
+        }
+        return Decoded { text: normalize(&text), warnings };
+    }
+
+    let declared = match label.to_ascii_lowercase().as_str() {
+        "" | "us-ascii" | "ascii" => None,
+        _ => match lookup_charset(label) {
+            Some(enc) => Some(enc),
+            None => {
+                warnings.push(format!("알 수 없는 문자셋 '{}' → 자동 추정", label));
+                None
+            }
+        },
+    };
+
+    if let Some(enc) = declared {
+        let (text, _, had_errors) = enc.decode(bytes);
+        if !had_errors {
+            return Decoded { text: normalize(&text), warnings };
+        }
+        // 선언과 실제가 다른 경우 (예: EUC-KR 선언 + UTF-8 본문)
+        let guessed = sniff(bytes);
+        if guessed.name() != enc.name() {
+            let (alt, _, alt_errors) = guessed.decode(bytes);
+            if !alt_errors {
+                warnings.push(format!(
+                    "선언된 문자셋 {} 와 실제 내용 불일치 → {} 로 디코딩",
+                    enc.name(),
+                    guessed.name()
+                ));
+                return Decoded { text: normalize(&alt), warnings };
+            }
+        }
+        warnings.push(format!("{} 디코딩 중 잘못된 바이트 대체", enc.name()));
+        return Decoded { text: normalize(&text), warnings };
+    }
+
+    let enc = sniff(bytes);
+    let (text, _, had_errors) = enc.decode(bytes);
+    if enc.name() != UTF_8.name() {
+        warnings.push(format!("문자셋 미지정 8비트 데이터 → {} 로 추정", enc.name()));
+    }
+    if had_errors {
+        warnings.push(format!("{} 디코딩 중 잘못된 바이트 대체", enc.name()));
+    }
+    Decoded { text: normalize(&text), warnings }
+}
+
+/// 문자셋 추정: 유효한 UTF-8 이면 UTF-8, 아니면 EUC-KR(CP949)
+fn sniff(bytes: &[u8]) -> &'static Encoding {
+    if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        EUC_KR
+    }
+}
+
+/// 유니코드 NFC 정규화 + 제어문자 제거 (탭/줄바꿈 제외)
+pub fn normalize(text: &str) -> String {
+    text.nfc()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .collect()
+}
+
+/// Q 인코딩(RFC 2047 4.2) 디코딩
+fn decode_q(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => out.push(b' '),
+            b'=' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'='),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// B 인코딩 디코딩 — 패딩 누락/공백 섞임 허용
+fn decode_b(text: &str) -> Option<Vec<u8>> {
+    let cleaned: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect();
+    STANDARD_NO_PAD.decode(cleaned.as_bytes()).ok()
+}
+
+/// RFC 5322 2.2.3 unfolding: CRLF(또는 LF) 바로 뒤에 공백/탭이 오면 줄바꿈만 제거
+pub fn unfold(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let eol = match &raw[i..] {
+            [b'\r', b'\n', ..] => 2,
+            [b'\n', ..] => 1,
+            _ => 0,
+        };
+        if eol > 0 && matches!(raw.get(i + eol), Some(b' ' | b'\t')) {
+            i += eol;
+            continue;
+        }
+        out.push(raw[i]);
+        i += 1;
+    }
+    out
+}
+
+/// 헤더 값(원본 바이트) 디코딩
+///
+/// - 접힌 줄(folding) 먼저 펴기
+/// - 인접한 encoded-word 사이 공백 제거 (RFC 2047 6.2)
+/// - 같은 문자셋의 인접 encoded-word 는 바이트를 이어 붙인 뒤 한 번에 디코딩
+///   (멀티바이트 문자가 단어 경계에서 잘린 메일 복구)
+/// - encoded-word 밖의 8비트 원문은 UTF-8 → EUC-KR 순으로 추정
+pub fn decode_header(raw: &[u8]) -> Decoded {
+    let mut warnings = Vec::new();
+    let raw = unfold(raw);
+
+    // 1) encoded-word 밖 8비트 데이터 처리
+    let text = match std::str::from_utf8(&raw) {
+        Ok(s) => s.to_string(),
+        Err(_) => {
+            let d = decode_bytes(&raw, None);
+            warnings.extend(d.warnings);
+            d.text
+        }
+    };
+
+    // 2) encoded-word 조각 나누기
+    let mut out = String::new();
+    let mut pending: Option<(String, Vec<u8>)> = None; // (charset, 누적 바이트)
+    let mut last = 0;
+
+    for cap in ENCODED_WORD.captures_iter(&text) {
+        let whole = cap.get(0).unwrap();
+        let between = &text[last..whole.start()];
+        // encoded-word 사이 공백만 있으면 무시
+        if !(pending.is_some() && between.trim().is_empty()) {
+            flush(&mut pending, &mut out, &mut warnings);
+            out.push_str(between);
+        }
+        last = whole.end();
+
+        // RFC 2231 언어 태그 제거 (charset*lang)
+        let charset = cap[1].split('*').next().unwrap_or("").to_string();
+        let payload = &cap[3];
+        let bytes = if cap[2].eq_ignore_ascii_case("b") {
+            match decode_b(payload) {
+                Some(b) => b,
+                None => {
+                    warnings.push(format!("잘못된 base64 encoded-word: {}", whole.as_str()));
+                    flush(&mut pending, &mut out, &mut warnings);
+                    out.push_str(whole.as_str());
+                    continue;
+                }
+            }
+        } else {
+            decode_q(payload)
+        };
+
+        match &mut pending {
+            Some((cs, buf)) if cs.eq_ignore_ascii_case(&charset) => buf.extend(bytes),
+            _ => {
+                flush(&mut pending, &mut out, &mut warnings);
+                pending = Some((charset, bytes));
+            }
+        }
+    }
+    flush(&mut pending, &mut out, &mut warnings);
+    out.push_str(&text[last..]);
+
+    // 3) 끝이 잘린 encoded-word (예: "=?UTF-8?B?7JWI" 로 끝남)
+    if let Some(pos) = out.rfind("=?") {
+        let tail = &out[pos..];
+        let parts: Vec<&str> = tail.splitn(4, '?').collect();
+        if parts.len() == 4 && !tail.ends_with("?=") {
+            let fixed = format!("{}?=", tail.trim_end());
+            if let Some(cap) = ENCODED_WORD.captures(&fixed) {
+                let bytes = if cap[2].eq_ignore_ascii_case("b") {
+                    decode_b(&cap[3])
+                } else {
+                    Some(decode_q(&cap[3]))
+                };
+                if let Some(bytes) = bytes {
+                    let d = decode_bytes(&bytes, Some(&cap[1]));
+                    warnings.push("닫히지 않은 encoded-word 복구".to_string());
+                    out = format!("{}{}", &out[..pos], d.text);
+                }
+            }
+        }
+    }
+
+    Decoded { text: normalize(out.trim()), warnings }
+}
+
+/// quoted-printable 본문 디코딩 (soft line break 포함, 잘린 끝부분 허용)
+pub fn decode_quoted_printable(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != b'=' {
+            out.push(input[i]);
+            i += 1;
+            continue;
+        }
+        match input.get(i + 1..i + 3) {
+            Some(b"\r\n") => i += 3,
+            Some([b'\n', _]) => i += 2,
+            Some(hex) => {
+                match std::str::from_utf8(hex).ok().and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => out.push(b),
+                    None => out.extend_from_slice(&input[i..i + 3]),
+                }
+                i += 3;
+            }
+            // "=\n" 또는 잘린 끝부분
+            None => {
+                if input.get(i + 1) != Some(&b'\n') {
+                    out.extend_from_slice(&input[i..]);
+                }
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// base64 본문 디코딩 — 줄바꿈/패딩을 무시하고, 마지막 2~3글자 조각도 디코딩
+///
+/// 부분 fetch 로 잘려 바이트를 만들 수 없는 1글자 꼬리만 버립니다.
+pub fn decode_base64_lenient(input: &[u8]) -> Vec<u8> {
+    let mut cleaned: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|b| b.is_ascii_alphanumeric() || *b == b'+' || *b == b'/')
+        .collect();
+    if cleaned.len() % 4 == 1 {
+        cleaned.pop();
+    }
+    LENIENT_BASE64.decode(&cleaned).unwrap_or_default()
+}
+
+/// 누적된 encoded-word 바이트를 디코딩해 출력에 붙임
+fn flush(pending: &mut Option<(String, Vec<u8>)>, out: &mut String, warnings: &mut Vec<String>) {
+    if let Some((charset, bytes)) = pending.take() {
+        let d = decode_bytes(&bytes, Some(&charset));
+        warnings.extend(d.warnings);
+        out.push_str(&d.text);
+    }
+}
+
+/// MIME 파트 본문을 선언된 문자셋(없으면 추정)으로 디코딩
+pub fn decode_part_body(part: &ParsedMail) -> Decoded {
+    match part.get_body_raw() {
+        Ok(bytes) => decode_bytes(&bytes, Some(&part.ctype.charset)),
+        Err(e) => Decoded {
+            text: String::new(),
+            warnings: vec![format!("전송 인코딩 해제 실패 ({}): {}", part.ctype.mimetype, e)],
+        },
+    }
+}
@@ -0,0 +1,220 @@
+// common/src/pop3.rs
+//
+// POP3 메일 소스 (RFC 1939 + UIDL)
+// POP3 에는 읽음 표시가 없으므로 UIDL 목록으로 이미 처리한 메일을 걸러 냅니다.
+// UIDL 은 저장이 끝난 메일만 `ack` 로 기록하므로, 기록 전에 실패하면 다음 확인에서 다시 가져옵니다.
+
+use crate::gmail::{parse_single_email, ParsedEmail};
+use anyhow::{anyhow, Context, Result};
+use native_tls::TlsConnector;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// 한 번에 가져올 최대 메일 수 (나머지는 다음 주기에)
+const MAX_PER_POLL: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct Pop3Config {
+    pub host: String,
+    pub port: u16,
+    /// false 면 평문 연결 (로컬 테스트 서버용)
+    pub tls: bool,
+    pub username: String,
+    pub password: String,
+    /// 처리한 UIDL 을 한 줄에 하나씩 기록할 파일 (재시작 후에도 중복 방지)
+    pub seen_file: Option<PathBuf>,
+    pub timeout: Duration,
+}
+
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+/// 블로킹 POP3 연결
+pub struct Pop3Client {
+    reader: BufReader<Box<dyn Stream>>,
+}
+
+impl Pop3Client {
+    /// 접속 + USER/PASS 로그인
+    pub fn connect(config: &Pop3Config) -> Result<Self> {
+        let addr = (config.host.as_str(), config.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("{} 주소 조회 실패", config.host))?;
+        let tcp = TcpStream::connect_timeout(&addr, config.timeout)
+            .with_context(|| format!("POP3 연결 실패: {}:{}", config.host, config.port))?;
+        tcp.set_read_timeout(Some(config.timeout))?;
+        tcp.set_write_timeout(Some(config.timeout))?;
+
+        let stream: Box<dyn Stream> = if config.tls {
+            let tls = TlsConnector::builder().build()?;
+            Box::new(
+                tls.connect(&config.host, tcp)
+                    .map_err(|e| anyhow!("POP3 TLS 실패: {}", e))?,
+            )
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut client = Pop3Client { reader: BufReader::new(stream) };
+        client.read_status().context("POP3 인사말")?;
+        client.command(&format!("USER {}", config.username))?;
+        client
+            .command(&format!("PASS {}", config.password))
+            .map_err(|_| anyhow!("POP3 로그인 실패: {}", config.username))?;
+        Ok(client)
+    }
+
+    /// 한 줄 응답 명령 (+OK 확인)
+    fn command(&mut self, cmd: &str) -> Result<String> {
+        let stream = self.reader.get_mut();
+        stream.write_all(cmd.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        self.read_status()
+    }
+
+    fn read_status(&mut self) -> Result<String> {
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        let line = line.trim_end().to_string();
+        match line.strip_prefix("+OK") {
+            Some(rest) => Ok(rest.trim().to_string()),
+            None => Err(anyhow!("POP3 오류 응답: {}", line)),
+        }
+    }
+
+    /// 여러 줄 응답 본문 ("." 줄까지, 점 스터핑 해제)
+    fn read_multiline(&mut self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                return Err(anyhow!("POP3 응답이 중간에 끊어졌습니다"));
+            }
+            let content = line.strip_suffix(b"\n").unwrap_or(&line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+            if content == b"." {
+                return Ok(out);
+            }
+            let content = content.strip_prefix(b".").unwrap_or(content);
+            out.extend_from_slice(content);
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+
+    /// UIDL: (메시지 번호, 고유 ID) 목록
+    pub fn uidl(&mut self) -> Result<Vec<(u32, String)>> {
+        self.command("UIDL")?;
+        let body = self.read_multiline()?;
+        Ok(String::from_utf8_lossy(&body)
+            .lines()
+            .filter_map(|line| {
+                let (n, uid) = line.trim().split_once(' ')?;
+                Some((n.parse().ok()?, uid.trim().to_string()))
+            })
+            .collect())
+    }
+
+    /// RETR: 메일 원문
+    pub fn retr(&mut self, msgno: u32) -> Result<Vec<u8>> {
+        self.command(&format!("RETR {}", msgno))?;
+        self.read_multiline()
+    }
+
+    pub fn quit(mut self) {
+        let _ = self.command("QUIT");
+    }
+}
+
+/// UIDL 기반 POP3 소스
+pub struct Pop3Source {
+    config: Pop3Config,
+    seen: HashSet<String>,
+}
+
+impl Pop3Source {
+    /// seen_file 이 있으면 이전에 처리한 UIDL 을 읽어 옴
+    pub fn new(config: Pop3Config) -> Self {
+        let seen = config
+            .seen_file
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|s| s.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+            .unwrap_or_default();
+        Pop3Source { config, seen }
+    }
+
+    /// 아직 기록하지 않은 메일 가져오기 (블로킹 풀에서 실행)
+    ///
+    /// 돌려준 메일은 `ack` 하기 전까지 다음 확인에서도 다시 나옵니다.
+    pub async fn fetch_new(&mut self) -> Result<Vec<ParsedEmail>> {
+        let config = self.config.clone();
+        // 작업이 중간에 죽어도 기록을 잃지 않도록 복사본을 넘김
+        let mut seen = self.seen.clone();
+        let (mails, pruned) = tokio::task::spawn_blocking(move || {
+            fetch_new_blocking(&config, &mut seen).map(|mails| (mails, seen))
+        })
+        .await
+        .map_err(|e| anyhow!("POP3 작업 실패: {}", e))??;
+        if pruned.len() != self.seen.len() {
+            self.seen = pruned;
+            self.save_seen();
+        }
+        Ok(mails)
+    }
+
+    /// 저장이 끝난 메일의 UIDL 기록
+    pub fn ack(&mut self, uids: &[String]) {
+        let before = self.seen.len();
+        self.seen.extend(uids.iter().cloned());
+        if self.seen.len() != before {
+            self.save_seen();
+        }
+    }
+
+    fn save_seen(&self) {
+        let Some(path) = &self.config.seen_file else { return };
+        let mut lines: Vec<&str> = self.seen.iter().map(|s| s.as_str()).collect();
+        lines.sort_unstable();
+        match fs::write(path, lines.join("\n")) {
+            Ok(()) => debug!("[POP3] UIDL 기록 {}건 저장", lines.len()),
+            Err(e) => warn!("[POP3] UIDL 기록 저장 실패 {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// 서버에서 사라진 UIDL 은 `seen` 에서 정리하고, 기록에 없는 메일을 받아 옴
+fn fetch_new_blocking(config: &Pop3Config, seen: &mut HashSet<String>) -> Result<Vec<ParsedEmail>> {
+    let mut client = Pop3Client::connect(config)?;
+    let list = client.uidl().context("UIDL 실패 (서버가 UIDL 을 지원해야 합니다)")?;
+
+    let on_server: HashSet<&str> = list.iter().map(|(_, u)| u.as_str()).collect();
+    seen.retain(|u| on_server.contains(u.as_str()));
+
+    let pending: Vec<&(u32, String)> = list.iter().filter(|(_, u)| !seen.contains(u)).take(MAX_PER_POLL).collect();
+    let mut mails = Vec::new();
+    for (msgno, uidl) in pending {
+        match client.retr(*msgno) {
+            Ok(raw) => {
+                let mut mail = parse_single_email(*msgno, &raw);
+                mail.uid = uidl.clone();
+                mail.mailbox = "INBOX".to_string();
+                for w in &mail.parse_warnings {
+                    warn!("[POP3] {} 파싱 경고: {}", uidl, w);
+                }
+                mails.push(mail);
+            }
+            Err(e) => warn!("[POP3] {} RETR 실패: {}", uidl, e),
+        }
+    }
+    client.quit();
+    info!("[POP3] {} 새 메일 {}건 (서버 전체 {}건)", config.username, mails.len(), list.len());
+    Ok(mails)
+}
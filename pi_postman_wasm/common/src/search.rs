@@ -0,0 +1,242 @@
+// common/src/search.rs
+//
+// 메일 검색 질의 문법
+//
+//   청구서 "결제 완료" from:bank category:finance after:2024-01-01 before:2024-02-01
+//
+// - 일반 단어: 제목/본문/보낸이/첨부 텍스트에서 접두어 일치
+// - "따옴표": 구문 일치
+// - from: / category: / before: / after: 필터 (값에도 따옴표 사용 가능, 그 밖의 key:"값" 은 오류)
+//
+// snippet 은 HTML 로 바로 넣을 수 있도록 본문을 이스케이프하고 일치 부분만 <mark> 로 감쌉니다.
+
+use crate::email::Email;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+
+/// 하이라이트 표시 (snippet 안에서 일치 부분을 감쌈)
+pub const MARK_START: &str = "<mark>";
+pub const MARK_END: &str = "</mark>";
+/// 이스케이프 전 원문에서 일치 구간을 표시하는 문자 (유니코드 사용자 영역)
+pub(crate) const RAW_MARK_START: char = '\u{E000}';
+pub(crate) const RAW_MARK_END: char = '\u{E001}';
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub phrases: Vec<String>,
+    pub from: Option<String>,
+    pub category: Option<String>,
+    /// 이 시각 이전 (미포함)
+    pub before: Option<DateTime<Utc>>,
+    /// 이 시각 이후 (포함)
+    pub after: Option<DateTime<Utc>>,
+}
+
+/// 검색 결과 한 건
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub from: String,
+    pub subject: String,
+    pub received_at: DateTime<Utc>,
+    pub category: Option<String>,
+    /// 일치 부분을 <mark> 로 감싼 본문 일부 (나머지는 HTML 이스케이프)
+    pub snippet: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SearchPage {
+    /// 조건에 맞는 전체 건수
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+impl SearchQuery {
+    /// 질의 문자열 파싱 (해석할 수 없는 날짜나 알 수 없는 필터는 오류)
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut q = SearchQuery::default();
+        for token in tokenize(input) {
+            let (key, value, quoted) = match token {
+                Token::Word(w) => match w.split_once(':') {
+                    Some((k, v)) if is_filter(k) => (Some(k.to_ascii_lowercase()), v.to_string(), false),
+                    _ => (None, w, false),
+                },
+                Token::Filter(k, _) if !is_filter(&k) => return Err(format!("알 수 없는 검색 필터: {}", k)),
+                Token::Filter(k, v) => (Some(k.to_ascii_lowercase()), v, true),
+                Token::Phrase(p) => (None, p, true),
+            };
+            let value = value.trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+            match key.as_deref() {
+                Some("from") => q.from = Some(value),
+                Some("category") => q.category = Some(value),
+                Some("before") => q.before = Some(parse_date(&value)?),
+                Some("after") => q.after = Some(parse_date(&value)?),
+                _ if quoted => q.phrases.push(value),
+                _ => q.terms.push(value),
+            }
+        }
+        Ok(q)
+    }
+
+    /// 단어/구문 조건이 있는지 (없으면 필터만으로 최신순 조회)
+    pub fn has_text(&self) -> bool {
+        !self.terms.is_empty() || !self.phrases.is_empty()
+    }
+
+    /// 메모리 저장소용 판정 (대소문자 무시 부분 일치)
+    pub fn matches(&self, email: &Email) -> bool {
+        let contains = |hay: &str, needle: &str| hay.to_lowercase().contains(&needle.to_lowercase());
+        if let Some(from) = &self.from {
+            if !contains(&email.from, from) {
+                return false;
+            }
+        }
+        if let Some(cat) = &self.category {
+            if !email.category.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(cat)) {
+                return false;
+            }
+        }
+        if self.before.is_some_and(|t| email.received_at >= t) || self.after.is_some_and(|t| email.received_at < t) {
+            return false;
+        }
+        let text = format!("{}\n{}\n{}\n{}", email.subject, email.body, email.from, email.attachment_text);
+        self.terms.iter().chain(&self.phrases).all(|t| contains(&text, t))
+    }
+
+    /// 메모리 저장소용 snippet: 첫 일치 부분 주변 텍스트
+    pub fn snippet(&self, email: &Email, width: usize) -> String {
+        let needles: Vec<String> = self.terms.iter().chain(&self.phrases).map(|t| t.to_lowercase()).collect();
+        let source = if email.body.trim().is_empty() { &email.attachment_text } else { &email.body };
+        let chars: Vec<char> = source.chars().collect();
+        let lower: Vec<char> = source.to_lowercase().chars().collect();
+        // to_lowercase 로 글자 수가 바뀌는 경우는 위치 계산을 포기하고 앞부분 사용
+        let start = if lower.len() == chars.len() {
+            needles
+                .iter()
+                .filter_map(|n| find_chars(&lower, &n.chars().collect::<Vec<_>>()))
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let from = start.saturating_sub(width / 3);
+        let to = (from + width).min(chars.len());
+        let mut out = String::new();
+        if from > 0 {
+            out.push('…');
+        }
+        let mut i = from;
+        while i < to {
+            let hit = needles.iter().find_map(|n| {
+                let n: Vec<char> = n.chars().collect();
+                (!n.is_empty() && lower.len() == chars.len() && lower[i..].starts_with(&n)).then_some(n.len())
+            });
+            match hit {
+                Some(len) => {
+                    let end = (i + len).min(chars.len());
+                    out.push(RAW_MARK_START);
+                    out.extend(&chars[i..end]);
+                    out.push(RAW_MARK_END);
+                    i = end;
+                }
+                None => {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+            }
+        }
+        if to < chars.len() {
+            out.push('…');
+        }
+        render_snippet(&out)
+    }
+}
+
+/// 일치 구간 표시가 들어간 원문 → HTML (본문은 이스케이프, 표시만 <mark>, 줄바꿈은 공백)
+pub(crate) fn render_snippet(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match c {
+            RAW_MARK_START => out.push_str(MARK_START),
+            RAW_MARK_END => out.push_str(MARK_END),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            '\r' | '\n' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn find_chars(hay: &[char], needle: &[char]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > hay.len() {
+        return None;
+    }
+    hay.windows(needle.len()).position(|w| w == needle)
+}
+
+fn is_filter(key: &str) -> bool {
+    matches!(key.to_ascii_lowercase().as_str(), "from" | "category" | "before" | "after")
+}
+
+/// YYYY-MM-DD (UTC 자정) 또는 RFC 3339
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| Utc.from_utc_datetime(&dt))
+        .ok_or_else(|| format!("날짜 형식 오류: {} (YYYY-MM-DD)", s))
+}
+
+enum Token {
+    Word(String),
+    Phrase(String),
+    /// key:"값"
+    Filter(String, String),
+}
+
+/// 공백 기준 분리, 따옴표 안은 한 덩어리
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '"' {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            tokens.push(Token::Phrase(phrase));
+            continue;
+        }
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            if c == '"' && word.ends_with(':') {
+                let value: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                word.pop();
+                tokens.push(Token::Filter(std::mem::take(&mut word), value));
+                break;
+            }
+            word.push(c);
+        }
+        if !word.is_empty() {
+            tokens.push(Token::Word(word));
+        }
+    }
+    tokens
+}
@@ -0,0 +1,106 @@
+// common/src/source.rs
+//
+// 메일 소스 공통 인터페이스: IMAP(Gmail) / POP3 / JMAP
+// 어떤 소스든 "새 메일 가져오기 → 다음 확인까지 대기" 로 같은 ParsedEmail 을 돌려줍니다.
+
+use crate::gmail::{mark_seen, AsyncSession, GmailConfig, ParsedEmail};
+use crate::jmap::{JmapConfig, JmapSource};
+use crate::pop3::{Pop3Config, Pop3Source};
+use anyhow::Result;
+use std::time::Duration;
+use tracing::warn;
+
+pub enum MailSource {
+    /// IMAP 메일함 하나 (연결은 확인할 때마다 새로 맺음)
+    Imap { config: GmailConfig, mailbox: String },
+    Pop3(Pop3Source),
+    Jmap(JmapSource),
+}
+
+impl MailSource {
+    pub fn imap(config: GmailConfig, mailbox: &str) -> Self {
+        MailSource::Imap { config, mailbox: mailbox.to_string() }
+    }
+
+    pub fn pop3(config: Pop3Config) -> Self {
+        MailSource::Pop3(Pop3Source::new(config))
+    }
+
+    pub fn jmap(config: JmapConfig) -> Result<Self> {
+        Ok(MailSource::Jmap(JmapSource::new(config)?))
+    }
+
+    /// 로그 표시용 종류 이름
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MailSource::Imap { .. } => "imap",
+            MailSource::Pop3(_) => "pop3",
+            MailSource::Jmap(_) => "jmap",
+        }
+    }
+
+    /// 아직 처리하지 않은 메일 가져오기
+    ///
+    /// 어느 소스든 여기서는 읽음 표시(POP3 는 UIDL 기록)를 하지 않으므로, 저장이 끝난 메일을
+    /// `ack` 로 알려야 합니다. 알리지 않은 메일은 다음 확인에서 다시 옵니다.
+    pub async fn fetch_new(&mut self) -> Result<Vec<ParsedEmail>> {
+        match self {
+            MailSource::Imap { config, mailbox } => {
+                let mut session = AsyncSession::connect(config).await?;
+                let mut mails = Vec::new();
+                let result = session
+                    .for_each_unseen(mailbox, None, |_| true, |em| {
+                        mails.push(em);
+                        // 여기서는 읽음 표시하지 않음 (ack 에서)
+                        std::future::ready(false)
+                    })
+                    .await;
+                session.logout().await;
+                // 도중에 끊겨도 이미 받은 메일은 넘김 (나머지는 UNSEEN 으로 남아 다음 확인에서 다시 옴)
+                if let Err(e) = result {
+                    if mails.is_empty() {
+                        return Err(e.into());
+                    }
+                    warn!("[IMAP] {} 조회 중단: {} — 받은 {}건만 처리", mailbox, e, mails.len());
+                }
+                Ok(mails)
+            }
+            MailSource::Pop3(src) => src.fetch_new().await,
+            MailSource::Jmap(src) => src.fetch_new().await,
+        }
+    }
+
+    /// 저장까지 끝난 메일 UID 를 읽음 표시 (IMAP \Seen / POP3 UIDL 기록 / JMAP $seen)
+    pub async fn ack(&mut self, uids: &[String]) -> Result<()> {
+        let (config, mailbox) = match self {
+            MailSource::Imap { config, mailbox } => (config, mailbox),
+            MailSource::Pop3(src) => {
+                src.ack(uids);
+                return Ok(());
+            }
+            MailSource::Jmap(src) => return src.ack(uids).await,
+        };
+        let uids: Vec<u32> = uids.iter().filter_map(|u| u.parse().ok()).collect();
+        if uids.is_empty() {
+            return Ok(());
+        }
+        let mut session = AsyncSession::connect(config).await?;
+        let mailbox = mailbox.clone();
+        let result = session
+            .run(move |s| {
+                s.select(&mailbox)?;
+                uids.iter().try_for_each(|&uid| mark_seen(s, uid))
+            })
+            .await;
+        session.logout().await;
+        Ok(result?)
+    }
+
+    /// 다음 확인까지 대기 (JMAP 은 푸시가 오면 바로 돌아옴)
+    pub async fn wait(&mut self, interval: Duration) {
+        match self {
+            MailSource::Jmap(src) => src.wait_for_change(interval).await,
+            _ => tokio::time::sleep(interval).await,
+        }
+    }
+}
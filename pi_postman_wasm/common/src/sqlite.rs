@@ -0,0 +1,452 @@
+// common/src/sqlite.rs
+//
+// SQLite 이메일 저장소 (스키마 버전은 PRAGMA user_version 으로 관리)
+
+use crate::email::Email;
+use crate::search::{render_snippet, SearchHit, SearchPage, SearchQuery, RAW_MARK_END, RAW_MARK_START};
+use crate::store::{Cursor, EmailStore, ListFilter, SortOrder};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension, Row, TransactionBehavior};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// 순서대로 적용할 마이그레이션 (인덱스 + 1 = 스키마 버전)
+const MIGRATIONS: &[&str] = &[
+    // v1: 기본 테이블 + 조회용 인덱스
+    "CREATE TABLE emails (
+        id           TEXT PRIMARY KEY,
+        from_addr    TEXT NOT NULL,
+        to_addr      TEXT NOT NULL,
+        subject      TEXT NOT NULL,
+        body         TEXT NOT NULL,
+        received_at  TEXT NOT NULL,
+        category     TEXT,
+        ai_processed INTEGER NOT NULL DEFAULT 0,
+        headers      TEXT NOT NULL DEFAULT '{}',
+        raw_sha256   TEXT
+    );
+    CREATE INDEX idx_emails_received_at ON emails(received_at);
+    CREATE INDEX idx_emails_category ON emails(category);
+    CREATE INDEX idx_emails_from ON emails(from_addr);",
+    // v2: 첨부 텍스트 + 전문 검색 (emails 를 외부 콘텐츠로 쓰는 FTS5, 트리거로 동기화)
+    "ALTER TABLE emails ADD COLUMN attachment_text TEXT NOT NULL DEFAULT '';
+    CREATE VIRTUAL TABLE emails_fts USING fts5(
+        subject, body, from_addr, attachment_text,
+        content='emails', content_rowid='rowid', tokenize='unicode61'
+    );
+    CREATE TRIGGER emails_fts_ai AFTER INSERT ON emails BEGIN
+        INSERT INTO emails_fts(rowid, subject, body, from_addr, attachment_text)
+        VALUES (new.rowid, new.subject, new.body, new.from_addr, new.attachment_text);
+    END;
+    CREATE TRIGGER emails_fts_ad AFTER DELETE ON emails BEGIN
+        INSERT INTO emails_fts(emails_fts, rowid, subject, body, from_addr, attachment_text)
+        VALUES ('delete', old.rowid, old.subject, old.body, old.from_addr, old.attachment_text);
+    END;
+    CREATE TRIGGER emails_fts_au AFTER UPDATE ON emails BEGIN
+        INSERT INTO emails_fts(emails_fts, rowid, subject, body, from_addr, attachment_text)
+        VALUES ('delete', old.rowid, old.subject, old.body, old.from_addr, old.attachment_text);
+        INSERT INTO emails_fts(rowid, subject, body, from_addr, attachment_text)
+        VALUES (new.rowid, new.subject, new.body, new.from_addr, new.attachment_text);
+    END;
+    INSERT INTO emails_fts(emails_fts) VALUES ('rebuild');",
+    // v3: 스레드 조회용 (headers JSON 에서 뽑은 가상 컬럼 + 인덱스)
+    "ALTER TABLE emails ADD COLUMN message_id TEXT
+        GENERATED ALWAYS AS (json_extract(headers, '$.message_id')) VIRTUAL;
+    ALTER TABLE emails ADD COLUMN in_reply_to TEXT
+        GENERATED ALWAYS AS (json_extract(headers, '$.in_reply_to')) VIRTUAL;
+    CREATE INDEX idx_emails_message_id ON emails(message_id);
+    CREATE INDEX idx_emails_in_reply_to ON emails(in_reply_to);",
+    // v4: FTS 가 가리킬 명시적 INTEGER PRIMARY KEY (암시적 rowid 는 VACUUM 때 바뀔 수 있음)
+    //     테이블을 새로 만들어 옮기고 FTS 와 트리거도 seq 기준으로 다시 만듦
+    "DROP TRIGGER emails_fts_ai;
+    DROP TRIGGER emails_fts_ad;
+    DROP TRIGGER emails_fts_au;
+    DROP TABLE emails_fts;
+    CREATE TABLE emails_v4 (
+        seq             INTEGER PRIMARY KEY,
+        id              TEXT NOT NULL UNIQUE,
+        from_addr       TEXT NOT NULL,
+        to_addr         TEXT NOT NULL,
+        subject         TEXT NOT NULL,
+        body            TEXT NOT NULL,
+        received_at     TEXT NOT NULL,
+        category        TEXT,
+        ai_processed    INTEGER NOT NULL DEFAULT 0,
+        headers         TEXT NOT NULL DEFAULT '{}',
+        raw_sha256      TEXT,
+        attachment_text TEXT NOT NULL DEFAULT '',
+        message_id      TEXT GENERATED ALWAYS AS (json_extract(headers, '$.message_id')) VIRTUAL,
+        in_reply_to     TEXT GENERATED ALWAYS AS (json_extract(headers, '$.in_reply_to')) VIRTUAL
+    );
+    INSERT INTO emails_v4 (seq, id, from_addr, to_addr, subject, body, received_at, category,
+                           ai_processed, headers, raw_sha256, attachment_text)
+        SELECT rowid, id, from_addr, to_addr, subject, body, received_at, category,
+               ai_processed, headers, raw_sha256, attachment_text FROM emails;
+    DROP TABLE emails;
+    ALTER TABLE emails_v4 RENAME TO emails;
+    CREATE INDEX idx_emails_received_at ON emails(received_at);
+    CREATE INDEX idx_emails_category ON emails(category);
+    CREATE INDEX idx_emails_from ON emails(from_addr);
+    CREATE INDEX idx_emails_message_id ON emails(message_id);
+    CREATE INDEX idx_emails_in_reply_to ON emails(in_reply_to);
+    CREATE VIRTUAL TABLE emails_fts USING fts5(
+        subject, body, from_addr, attachment_text,
+        content='emails', content_rowid='seq', tokenize='unicode61'
+    );
+    CREATE TRIGGER emails_fts_ai AFTER INSERT ON emails BEGIN
+        INSERT INTO emails_fts(rowid, subject, body, from_addr, attachment_text)
+        VALUES (new.seq, new.subject, new.body, new.from_addr, new.attachment_text);
+    END;
+    CREATE TRIGGER emails_fts_ad AFTER DELETE ON emails BEGIN
+        INSERT INTO emails_fts(emails_fts, rowid, subject, body, from_addr, attachment_text)
+        VALUES ('delete', old.seq, old.subject, old.body, old.from_addr, old.attachment_text);
+    END;
+    CREATE TRIGGER emails_fts_au AFTER UPDATE ON emails BEGIN
+        INSERT INTO emails_fts(emails_fts, rowid, subject, body, from_addr, attachment_text)
+        VALUES ('delete', old.seq, old.subject, old.body, old.from_addr, old.attachment_text);
+        INSERT INTO emails_fts(rowid, subject, body, from_addr, attachment_text)
+        VALUES (new.seq, new.subject, new.body, new.from_addr, new.attachment_text);
+    END;
+    INSERT INTO emails_fts(emails_fts) VALUES ('rebuild');",
+];
+
+const COLUMNS: &str =
+    "id, from_addr, to_addr, subject, body, received_at, category, ai_processed, headers, raw_sha256, \
+     attachment_text";
+
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// 파일을 열고 (없으면 생성) 마이그레이션 적용
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path).with_context(|| format!("SQLite 열기 실패: {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        Self::from_connection(conn)
+    }
+
+    /// 메모리 DB (테스트용)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(SqliteStore { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| anyhow!("SQLite 연결 잠금 실패"))
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current: i64 = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    let current = current.max(0) as usize;
+    if current > MIGRATIONS.len() {
+        return Err(anyhow!(
+            "DB 스키마 버전({})이 프로그램({})보다 높습니다",
+            current,
+            MIGRATIONS.len()
+        ));
+    }
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)
+            .with_context(|| format!("마이그레이션 v{} 실패", i + 1))?;
+        tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+        tx.commit()?;
+        tracing::info!("[Store] 스키마 v{} 적용", i + 1);
+    }
+    Ok(())
+}
+
+/// 정렬 가능한 고정 형식 (UTC, 마이크로초)
+pub(crate) fn format_time(t: &DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+pub(crate) fn row_to_email(row: &Row) -> rusqlite::Result<Email> {
+    let received_at: String = row.get(5)?;
+    let headers: String = row.get(8)?;
+    Ok(Email {
+        id: row.get(0)?,
+        from: row.get(1)?,
+        to: row.get(2)?,
+        subject: row.get(3)?,
+        body: row.get(4)?,
+        received_at: DateTime::parse_from_rfc3339(&received_at)
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_default(),
+        category: row.get(6)?,
+        ai_processed: row.get(7)?,
+        headers: serde_json::from_str(&headers).unwrap_or_default(),
+        raw_sha256: row.get(9)?,
+        attachment_text: row.get(10)?,
+    })
+}
+
+/// id 가 같은 행을 이메일 값으로 교체
+fn update_row(conn: &Connection, email: &Email) -> Result<()> {
+    let changed = conn.execute(
+        "UPDATE emails SET from_addr = ?2, to_addr = ?3, subject = ?4, body = ?5, received_at = ?6,
+            category = ?7, ai_processed = ?8, headers = ?9, raw_sha256 = ?10, attachment_text = ?11
+         WHERE id = ?1",
+        params![
+            email.id,
+            email.from,
+            email.to,
+            email.subject,
+            email.body,
+            format_time(&email.received_at),
+            email.category,
+            email.ai_processed,
+            serde_json::to_string(&email.headers)?,
+            email.raw_sha256,
+            email.attachment_text,
+        ],
+    )?;
+    if changed == 0 {
+        return Err(anyhow!("이메일을 찾을 수 없음: {}", email.id));
+    }
+    Ok(())
+}
+
+impl EmailStore for SqliteStore {
+    fn insert(&self, email: &Email) -> Result<()> {
+        self.conn()?.execute(
+            &format!("INSERT INTO emails ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)", COLUMNS),
+            params![
+                email.id,
+                email.from,
+                email.to,
+                email.subject,
+                email.body,
+                format_time(&email.received_at),
+                email.category,
+                email.ai_processed,
+                serde_json::to_string(&email.headers)?,
+                email.raw_sha256,
+                email.attachment_text,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Email>> {
+        let email = self
+            .conn()?
+            .query_row(
+                &format!("SELECT {} FROM emails WHERE id = ?1", COLUMNS),
+                params![id],
+                row_to_email,
+            )
+            .optional()?;
+        Ok(email)
+    }
+
+    fn update(&self, email: &Email) -> Result<()> {
+        let conn = self.conn()?;
+        update_row(&conn, email)
+    }
+
+    fn modify(&self, id: &str, f: Box<dyn FnOnce(&mut Email) + '_>) -> Result<Email> {
+        let mut conn = self.conn()?;
+        // 다른 프로세스가 같은 DB 를 쓰더라도 읽은 뒤 쓰기 전까지 끼어들지 못하도록 IMMEDIATE
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut email = tx
+            .query_row(
+                &format!("SELECT {} FROM emails WHERE id = ?1", COLUMNS),
+                params![id],
+                row_to_email,
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("이메일을 찾을 수 없음: {}", id))?;
+        f(&mut email);
+        update_row(&tx, &email)?;
+        tx.commit()?;
+        Ok(email)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool> {
+        Ok(self.conn()?.execute("DELETE FROM emails WHERE id = ?1", params![id])? > 0)
+    }
+
+    fn count(&self) -> Result<usize> {
+        let n: i64 = self.conn()?.query_row("SELECT COUNT(*) FROM emails", [], |r| r.get(0))?;
+        Ok(n as usize)
+    }
+
+    fn by_message_id(&self, message_id: &str) -> Result<Option<Email>> {
+        let email = self
+            .conn()?
+            .query_row(
+                &format!("SELECT {} FROM emails WHERE message_id = ?1 ORDER BY received_at LIMIT 1", COLUMNS),
+                params![message_id],
+                row_to_email,
+            )
+            .optional()?;
+        Ok(email)
+    }
+
+    fn replies_to(&self, message_id: &str) -> Result<Vec<Email>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM emails WHERE in_reply_to = ?1 ORDER BY received_at",
+            COLUMNS
+        ))?;
+        let emails = stmt
+            .query_map(params![message_id], row_to_email)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(emails)
+    }
+
+    fn list(&self, filter: &ListFilter, cursor: Option<&Cursor>, limit: usize) -> Result<Vec<Email>> {
+        let mut conditions = Vec::new();
+        let mut args: Vec<Value> = Vec::new();
+        if let Some(cat) = &filter.category {
+            conditions.push("category = ? COLLATE NOCASE");
+            args.push(Value::Text(cat.clone()));
+        }
+        if let Some(from) = &filter.from {
+            conditions.push("from_addr LIKE ? ESCAPE '\\'");
+            args.push(Value::Text(format!("%{}%", escape_like(from))));
+        }
+        if let Some(since) = &filter.since {
+            conditions.push("received_at >= ?");
+            args.push(Value::Text(format_time(since)));
+        }
+        if let Some(until) = &filter.until {
+            conditions.push("received_at < ?");
+            args.push(Value::Text(format_time(until)));
+        }
+        if let Some(processed) = filter.processed {
+            conditions.push("ai_processed = ?");
+            args.push(Value::Integer(processed as i64));
+        }
+        let (direction, compare) = match filter.order {
+            SortOrder::NewestFirst => ("DESC", "(received_at, id) < (?, ?)"),
+            SortOrder::OldestFirst => ("ASC", "(received_at, id) > (?, ?)"),
+        };
+        if let Some(c) = cursor {
+            conditions.push(compare);
+            args.push(Value::Text(format_time(&c.received_at)));
+            args.push(Value::Text(c.id.clone()));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        args.push(Value::Integer(limit as i64));
+
+        let sql = format!(
+            "SELECT {} FROM emails {} ORDER BY received_at {d}, id {d} LIMIT ?",
+            COLUMNS,
+            where_clause,
+            d = direction
+        );
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let emails = stmt
+            .query_map(params_from_iter(args.iter()), row_to_email)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(emails)
+    }
+
+    fn search(&self, query: &SearchQuery, limit: usize, offset: usize) -> Result<SearchPage> {
+        let mut conditions = Vec::new();
+        let mut args: Vec<Value> = Vec::new();
+        if query.has_text() {
+            conditions.push("emails_fts MATCH ?".to_string());
+            args.push(Value::Text(fts_expression(query)));
+        }
+        if let Some(from) = &query.from {
+            conditions.push("e.from_addr LIKE ? ESCAPE '\\'".to_string());
+            args.push(Value::Text(format!("%{}%", escape_like(from))));
+        }
+        if let Some(cat) = &query.category {
+            conditions.push("e.category = ? COLLATE NOCASE".to_string());
+            args.push(Value::Text(cat.clone()));
+        }
+        if let Some(before) = &query.before {
+            conditions.push("e.received_at < ?".to_string());
+            args.push(Value::Text(format_time(before)));
+        }
+        if let Some(after) = &query.after {
+            conditions.push("e.received_at >= ?".to_string());
+            args.push(Value::Text(format_time(after)));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        // 단어 검색이면 FTS 조인 + bm25 순, 아니면 최신순
+        let (from_clause, snippet, order) = if query.has_text() {
+            (
+                "emails e JOIN emails_fts ON emails_fts.rowid = e.seq",
+                // 표시 문자로 감싼 뒤 Rust 에서 이스케이프하고 <mark> 로 바꿈
+                format!("snippet(emails_fts, -1, '{}', '{}', '…', 24)", RAW_MARK_START, RAW_MARK_END),
+                "bm25(emails_fts), e.received_at DESC",
+            )
+        } else {
+            ("emails e", "substr(e.body, 1, 160)".to_string(), "e.received_at DESC")
+        };
+
+        let conn = self.conn()?;
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} {}", from_clause, where_clause),
+            params_from_iter(args.iter()),
+            |r| r.get(0),
+        )?;
+
+        let sql = format!(
+            "SELECT e.id, e.from_addr, e.subject, e.received_at, e.category, {}
+             FROM {} {} ORDER BY {} LIMIT ? OFFSET ?",
+            snippet, from_clause, where_clause, order
+        );
+        args.push(Value::Integer(limit as i64));
+        args.push(Value::Integer(offset as i64));
+        let mut stmt = conn.prepare(&sql)?;
+        let hits = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                let received_at: String = row.get(3)?;
+                let snippet: String = row.get(5)?;
+                Ok(SearchHit {
+                    id: row.get(0)?,
+                    from: row.get(1)?,
+                    subject: row.get(2)?,
+                    received_at: DateTime::parse_from_rfc3339(&received_at)
+                        .map(|t| t.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                    category: row.get(4)?,
+                    snippet: render_snippet(&snippet),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(SearchPage { total: total as usize, hits })
+    }
+}
+
+/// 검색 질의 → FTS5 MATCH 식 (단어는 접두어, 구문은 그대로, 모두 AND)
+fn fts_expression(query: &SearchQuery) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    query
+        .terms
+        .iter()
+        .map(|t| format!("{}*", quote(t)))
+        .chain(query.phrases.iter().map(|p| quote(p)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
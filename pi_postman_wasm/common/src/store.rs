@@ -0,0 +1,249 @@
+// common/src/store.rs
+//
+// 이메일 저장소 추상화: 기본은 메모리, native 에서는 SQLite 로 교체 가능
+
+use crate::email::Email;
+use crate::search::{SearchHit, SearchPage, SearchQuery};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+lazy_static::lazy_static! {
+    static ref STORE: RwLock<Arc<dyn EmailStore>> = RwLock::new(Arc::new(MemoryStore::default()));
+}
+
+/// 목록 조회 정렬 (수신 시각 기준, 같으면 id 순)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// 목록 조회 조건
+#[derive(Clone, Debug, Default)]
+pub struct ListFilter {
+    /// 분류 카테고리 (대소문자 무시)
+    pub category: Option<String>,
+    /// 보낸이 부분 일치
+    pub from: Option<String>,
+    /// 이 시각 이후 (포함)
+    pub since: Option<DateTime<Utc>>,
+    /// 이 시각 이전 (미포함)
+    pub until: Option<DateTime<Utc>>,
+    /// AI 분류 완료 여부
+    pub processed: Option<bool>,
+    pub order: SortOrder,
+}
+
+/// 커서: 직전 페이지 마지막 항목의 (수신 시각, id)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub received_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl ListFilter {
+    pub fn matches(&self, email: &Email) -> bool {
+        self.category
+            .as_deref()
+            .is_none_or(|c| email.category.as_deref().is_some_and(|x| x.eq_ignore_ascii_case(c)))
+            && self
+                .from
+                .as_deref()
+                .is_none_or(|f| email.from.to_lowercase().contains(&f.to_lowercase()))
+            && self.since.is_none_or(|t| email.received_at >= t)
+            && self.until.is_none_or(|t| email.received_at < t)
+            && self.processed.is_none_or(|p| email.ai_processed == p)
+    }
+}
+
+/// 이메일 저장소
+pub trait EmailStore: Send + Sync {
+    fn insert(&self, email: &Email) -> Result<()>;
+    fn get(&self, id: &str) -> Result<Option<Email>>;
+    /// 같은 id 의 이메일을 통째로 교체 (없으면 오류)
+    fn update(&self, email: &Email) -> Result<()>;
+    /// 읽기 → 수정 → 쓰기를 원자적으로 수행하고 수정된 값 반환 (없으면 오류)
+    ///
+    /// 동시에 들어온 수정이 서로의 변경을 덮어쓰지 않도록 `f` 실행 중에는 다른 쓰기를 막습니다.
+    fn modify(&self, id: &str, f: Box<dyn FnOnce(&mut Email) + '_>) -> Result<Email>;
+    /// 삭제했으면 true
+    fn delete(&self, id: &str) -> Result<bool>;
+    fn count(&self) -> Result<usize>;
+    /// Message-ID 로 조회 (꺾쇠 없이)
+    fn by_message_id(&self, message_id: &str) -> Result<Option<Email>>;
+    /// In-Reply-To 가 이 Message-ID 인 답장들 (수신 순)
+    fn replies_to(&self, message_id: &str) -> Result<Vec<Email>>;
+    /// 조건에 맞는 메일을 정렬 순서대로 `cursor` 다음부터 최대 `limit` 개
+    fn list(&self, filter: &ListFilter, cursor: Option<&Cursor>, limit: usize) -> Result<Vec<Email>>;
+    /// 전문 검색 (단어가 없으면 필터만 적용해 최신순)
+    fn search(&self, query: &SearchQuery, limit: usize, offset: usize) -> Result<SearchPage>;
+}
+
+/// 프로세스 메모리 저장소 (재시작하면 사라짐, 테스트/wasm 용)
+#[derive(Default)]
+pub struct MemoryStore {
+    emails: Mutex<HashMap<String, Email>>,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, Email>>> {
+        self.emails.lock().map_err(|_| anyhow!("이메일 저장소 잠금 실패"))
+    }
+}
+
+impl EmailStore for MemoryStore {
+    fn insert(&self, email: &Email) -> Result<()> {
+        self.lock()?.insert(email.id.clone(), email.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Email>> {
+        Ok(self.lock()?.get(id).cloned())
+    }
+
+    fn update(&self, email: &Email) -> Result<()> {
+        let mut emails = self.lock()?;
+        let slot = emails
+            .get_mut(&email.id)
+            .ok_or_else(|| anyhow!("이메일을 찾을 수 없음: {}", email.id))?;
+        *slot = email.clone();
+        Ok(())
+    }
+
+    fn modify(&self, id: &str, f: Box<dyn FnOnce(&mut Email) + '_>) -> Result<Email> {
+        let mut emails = self.lock()?;
+        let email = emails.get_mut(id).ok_or_else(|| anyhow!("이메일을 찾을 수 없음: {}", id))?;
+        f(email);
+        Ok(email.clone())
+    }
+
+    fn delete(&self, id: &str) -> Result<bool> {
+        Ok(self.lock()?.remove(id).is_some())
+    }
+
+    fn count(&self) -> Result<usize> {
+        Ok(self.lock()?.len())
+    }
+
+    fn by_message_id(&self, message_id: &str) -> Result<Option<Email>> {
+        let emails = self.lock()?;
+        Ok(emails
+            .values()
+            .filter(|e| e.headers.message_id.as_deref() == Some(message_id))
+            .min_by_key(|e| e.received_at)
+            .cloned())
+    }
+
+    fn replies_to(&self, message_id: &str) -> Result<Vec<Email>> {
+        let emails = self.lock()?;
+        let mut replies: Vec<Email> = emails
+            .values()
+            .filter(|e| e.headers.in_reply_to.as_deref() == Some(message_id))
+            .cloned()
+            .collect();
+        replies.sort_by_key(|e| e.received_at);
+        Ok(replies)
+    }
+
+    fn list(&self, filter: &ListFilter, cursor: Option<&Cursor>, limit: usize) -> Result<Vec<Email>> {
+        let emails = self.lock()?;
+        let key = |e: &Email| (e.received_at, e.id.clone());
+        let mut matched: Vec<&Email> = emails
+            .values()
+            .filter(|e| filter.matches(e))
+            .filter(|e| match (cursor, filter.order) {
+                (None, _) => true,
+                (Some(c), SortOrder::NewestFirst) => key(e) < (c.received_at, c.id.clone()),
+                (Some(c), SortOrder::OldestFirst) => key(e) > (c.received_at, c.id.clone()),
+            })
+            .collect();
+        matched.sort_by_key(|e| key(e));
+        if filter.order == SortOrder::NewestFirst {
+            matched.reverse();
+        }
+        Ok(matched.into_iter().take(limit).cloned().collect())
+    }
+
+    fn search(&self, query: &SearchQuery, limit: usize, offset: usize) -> Result<SearchPage> {
+        let emails = self.lock()?;
+        let mut matched: Vec<&Email> = emails.values().filter(|e| query.matches(e)).collect();
+        matched.sort_by_key(|e| std::cmp::Reverse(e.received_at));
+        let hits = matched
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|e| SearchHit {
+                id: e.id.clone(),
+                from: e.from.clone(),
+                subject: e.subject.clone(),
+                received_at: e.received_at,
+                category: e.category.clone(),
+                snippet: query.snippet(e, 160),
+            })
+            .collect();
+        Ok(SearchPage { total: matched.len(), hits })
+    }
+}
+
+/// 같은 스레드의 다른 메일 (References/In-Reply-To 로 조상, 답장 체인으로 자손을 찾음)
+///
+/// 수신 순으로 정렬하고, 많으면 가장 최근 `limit` 개만 남깁니다.
+pub fn thread_of(store: &dyn EmailStore, email: &Email, limit: usize) -> Result<Vec<Email>> {
+    let mut found: HashMap<String, Email> = HashMap::new();
+    let mut queue: Vec<String> = Vec::new();
+    let ancestors = email.headers.references.iter().chain(email.headers.in_reply_to.iter());
+    for mid in ancestors {
+        if let Some(e) = store.by_message_id(mid)? {
+            found.insert(e.id.clone(), e);
+        }
+        queue.push(mid.clone());
+    }
+    queue.extend(email.headers.message_id.clone());
+
+    // 답장 체인을 따라 내려감 (순환/폭주 방지로 방문 수 제한)
+    let mut visited = std::collections::HashSet::new();
+    while let Some(mid) = queue.pop() {
+        if !visited.insert(mid.clone()) || visited.len() > limit * 4 + 16 {
+            continue;
+        }
+        for reply in store.replies_to(&mid)? {
+            queue.extend(reply.headers.message_id.clone());
+            found.insert(reply.id.clone(), reply);
+        }
+    }
+
+    found.remove(&email.id);
+    let mut thread: Vec<Email> = found.into_values().collect();
+    thread.sort_by_key(|e| (e.received_at, e.id.clone()));
+    let skip = thread.len().saturating_sub(limit);
+    Ok(thread.split_off(skip))
+}
+
+/// 현재 전역 저장소
+pub fn store() -> Arc<dyn EmailStore> {
+    STORE.read().map(|s| s.clone()).unwrap_or_else(|e| e.into_inner().clone())
+}
+
+/// 전역 저장소 교체 (프로세스 시작 시 한 번)
+pub fn set_store(store: Arc<dyn EmailStore>) {
+    match STORE.write() {
+        Ok(mut s) => *s = store,
+        Err(e) => *e.into_inner() = store,
+    }
+}
+
+/// EMAIL_DB_PATH 가 있으면 SQLite 저장소 사용, 없으면 메모리 저장소 유지
+#[cfg(feature = "native")]
+pub fn init_from_env() -> Result<()> {
+    let Ok(path) = std::env::var("EMAIL_DB_PATH") else {
+        tracing::info!("[Store] EMAIL_DB_PATH 미설정 — 메모리 저장소 사용");
+        return Ok(());
+    };
+    let sqlite = crate::sqlite::SqliteStore::open(&path)?;
+    tracing::info!("[Store] SQLite 저장소 사용: {}", path);
+    set_store(Arc::new(sqlite));
+    Ok(())
+}
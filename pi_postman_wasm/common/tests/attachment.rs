@@ -0,0 +1,56 @@
+// common/tests/attachment.rs
+//
+// 첨부 텍스트 추출 — 손상된 파일, 압축 폭탄, 저장용 텍스트 예산
+
+#![cfg(feature = "native")]
+
+use common::attachment::{attachments_text, Attachment};
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+
+#[test]
+fn malformed_pdf_sets_error_instead_of_panicking() {
+    let bytes = b"%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R >>\nxref\n0 1\ntrailer << /Root 9 0 R >>\n%%EOF";
+    let att = Attachment::from_bytes("broken.pdf", "application/pdf", bytes, None);
+    assert!(att.text.is_none());
+    assert!(att.error.as_deref().is_some_and(|e| e.contains("PDF")), "{:?}", att.error);
+}
+
+#[test]
+fn docx_entry_over_limit_is_rejected() {
+    // 압축하면 수십 KB 지만 풀면 40MB
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("word/document.xml", SimpleFileOptions::default()).unwrap();
+    let chunk = vec![b' '; 1024 * 1024];
+    for _ in 0..40 {
+        zip.write_all(&chunk).unwrap();
+    }
+    let bytes = zip.finish().unwrap().into_inner();
+    assert!(bytes.len() < 1024 * 1024);
+
+    let att = Attachment::from_bytes("bomb.docx", "", &bytes, None);
+    assert!(att.text.is_none());
+    assert!(att.error.as_deref().is_some_and(|e| e.contains("초과")), "{:?}", att.error);
+}
+
+fn text_attachment(name: &str, text: &str) -> Attachment {
+    Attachment::from_bytes(name, "text/plain", text.as_bytes(), Some("utf-8"))
+}
+
+#[test]
+fn stored_attachment_text_stays_within_budget() {
+    let atts = vec![
+        text_attachment("a.txt", "첫 번째"),
+        Attachment::from_bytes("logo.png", "image/png", b"not an image", None),
+        text_attachment("big.txt", &"가".repeat(10_000)),
+        text_attachment("c.txt", "뒤쪽"),
+    ];
+    let all = attachments_text(&atts[..1], 4000);
+    assert_eq!(all, "[a.txt]\n첫 번째");
+
+    let text = attachments_text(&atts, 200);
+    assert!(text.chars().count() <= 200, "{}", text.chars().count());
+    assert!(text.starts_with("[a.txt]\n첫 번째\n\n[big.txt]\n가가"));
+    assert!(text.ends_with('…'));
+    assert!(!text.contains("c.txt"));
+}
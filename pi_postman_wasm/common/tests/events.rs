@@ -0,0 +1,30 @@
+// common/tests/events.rs
+//
+// 이벤트 버스 이어 받기 테스트 (재시작 전 ID 로 이어 받으면 gap)
+
+#![cfg(feature = "native")]
+
+use common::email::Email;
+use common::events::{publish, subscribe, EventKind};
+
+#[test]
+fn resume_and_restart_gap() {
+    let email = Email::new("a@example.com", "b@example.com", "제목", "본문");
+    for _ in 0..5 {
+        publish(EventKind::Received, &email, None);
+    }
+    let ids: Vec<u64> = subscribe(Some(0)).replay.iter().map(|e| e.id).collect();
+    assert_eq!(ids.len(), 5);
+    // 시작 시각 기준이라 이전 프로세스의 작은 ID 와 겹치지 않음
+    assert!(ids[0] > 1 << 40);
+
+    // 같은 프로세스 안에서는 빠짐없이 이어 받음
+    let sub = subscribe(Some(ids[1]));
+    assert!(!sub.gap);
+    assert_eq!(sub.replay.iter().map(|e| e.id).collect::<Vec<_>>(), ids[2..].to_vec());
+
+    // 재시작 전에 받은 ID 면 gap 과 함께 버퍼 전체를 재생
+    let sub = subscribe(Some(42));
+    assert!(sub.gap);
+    assert_eq!(sub.replay.len(), 5);
+}
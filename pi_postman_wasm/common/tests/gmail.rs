@@ -0,0 +1,52 @@
+// common/tests/gmail.rs
+//
+// 분류 라벨 이름 만들기와 IMAP 인코딩 테스트
+
+#![cfg(feature = "native")]
+
+use common::gmail::{encode_mailbox_name, quote_imap, title_case, LabelConfig};
+use std::collections::HashMap;
+
+fn config(prefix: &str, map: &[(&str, &str)]) -> LabelConfig {
+    LabelConfig {
+        prefix: prefix.to_string(),
+        mapping: map.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+        dry_run: false,
+    }
+}
+
+#[test]
+fn label_for_uses_mapping_then_title_case() {
+    let c = config("AI", &[("INVOICE", "청구서")]);
+    assert_eq!(c.label_for("invoice"), "AI/청구서");
+    assert_eq!(c.label_for(" SPAM "), "AI/Spam");
+    assert_eq!(c.label_for("긴급"), "AI/긴급");
+    assert_eq!(c.label_for(""), "AI/Uncategorized");
+    assert_eq!(config("", &[]).label_for("NEWS"), "News");
+}
+
+#[test]
+fn title_case_lowers_the_rest() {
+    assert_eq!(title_case("SPAM"), "Spam");
+    assert_eq!(title_case("spam mail"), "Spam mail");
+    assert_eq!(title_case("긴급"), "긴급");
+    assert_eq!(title_case(""), "Uncategorized");
+}
+
+#[test]
+fn quote_imap_escapes_quotes_and_backslashes() {
+    assert_eq!(quote_imap("AI/Spam"), r#""AI/Spam""#);
+    assert_eq!(quote_imap(r#"a"b\c"#), r#""a\"b\\c""#);
+}
+
+#[test]
+fn mailbox_names_are_modified_utf7() {
+    // RFC 3501 §5.1.3 예시
+    assert_eq!(encode_mailbox_name("~peter/mail/台北/日本語"), "~peter/mail/&U,BTFw-/&ZeVnLIqe-");
+    assert_eq!(encode_mailbox_name("AI/Spam"), "AI/Spam");
+    assert_eq!(encode_mailbox_name("AI/긴급"), "AI/&rjSuCQ-");
+    assert_eq!(encode_mailbox_name("R&D"), "R&-D");
+    assert_eq!(encode_mailbox_name("청구서 & 영수증"), "&zK2tbMEc- &- &xgHCGMmd-");
+    // BMP 밖 문자는 서로게이트 쌍으로
+    assert_eq!(encode_mailbox_name("😀"), "&2D3eAA-");
+}
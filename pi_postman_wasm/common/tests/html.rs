@@ -0,0 +1,21 @@
+// common/tests/html.rs
+//
+// HTML → 텍스트 변환 회귀 테스트
+
+#![cfg(feature = "native")]
+
+use common::html::html_to_text;
+
+#[test]
+fn link_after_pre_with_trailing_spaces() {
+    // </pre> 뒤 줄바꿈이 "x   " 의 공백을 잘라 링크 시작 위치가 문자 중간을 가리키던 문제
+    let r = html_to_text("<pre>x   </pre><a href=u>한</a>");
+    assert_eq!(r.links, ["u"]);
+    assert!(r.text.contains("한[1]"), "{:?}", r.text);
+}
+
+#[test]
+fn link_text_equal_to_href_has_no_marker() {
+    let r = html_to_text("<p>see</p><a href=\"https://example.com\">https://example.com</a>");
+    assert!(r.text.ends_with("https://example.com"), "{:?}", r.text);
+}
@@ -0,0 +1,190 @@
+// common/tests/jmap.rs
+//
+// 미리 정해 둔 응답을 돌려주는 JMAP 서버로 JmapSource 확인
+
+#![cfg(feature = "native")]
+
+use common::jmap::{JmapConfig, JmapSource};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// 받은 API 요청 본문 (methodCalls)
+type Calls = Arc<Mutex<Vec<Value>>>;
+
+fn spawn_server(calls: Calls) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let origin = base.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (calls, origin) = (calls.clone(), origin.clone());
+            thread::spawn(move || serve(stream, &origin, calls));
+        }
+    });
+    base
+}
+
+fn serve(stream: TcpStream, origin: &str, calls: Calls) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut length = 0;
+    let mut authorized = false;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap_or((line, ""));
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => length = value.trim().parse().unwrap(),
+            "authorization" => authorized = value.trim() == "Bearer token",
+            _ => {}
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+    let (status, content_type, payload) = if !authorized {
+        ("401 Unauthorized", "text/plain", b"unauthorized".to_vec())
+    } else if path == "/session" {
+        let session = json!({
+            "apiUrl": format!("{}/api", origin),
+            "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", origin),
+            "primaryAccounts": { "urn:ietf:params:jmap:mail": "acc1" }
+        });
+        ("200 OK", "application/json", session.to_string().into_bytes())
+    } else if path == "/api" {
+        let request: Value = serde_json::from_slice(&body).unwrap();
+        let mut calls = calls.lock().unwrap();
+        let m1_seen = calls.iter().flat_map(|c| c.as_array().unwrap()).any(|c| c[0] == "Email/set");
+        calls.push(request["methodCalls"].clone());
+        let response = api_response(&request["methodCalls"], m1_seen);
+        ("200 OK", "application/json", response.to_string().into_bytes())
+    } else if let Some(rest) = path.strip_prefix("/download/acc1/") {
+        let raw = match rest.split('/').next() {
+            Some("blob-1") => "Subject: First\r\n\r\nfirst body\r\n",
+            Some("blob-2") => "Subject: Second\r\n\r\nsecond body\r\n",
+            _ => "",
+        };
+        ("200 OK", "message/rfc822", raw.as_bytes().to_vec())
+    } else {
+        ("404 Not Found", "text/plain", b"not found".to_vec())
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        payload.len()
+    );
+    writer.write_all(head.as_bytes()).unwrap();
+    writer.write_all(&payload).unwrap();
+}
+
+/// inbox 에 안 읽은 메일 두 통 — m1 은 $seen 표시되면 빠지고, m2 는 표시가 거부되어 계속 남음
+fn api_response(calls: &Value, m1_seen: bool) -> Value {
+    let (ids, list) = if m1_seen {
+        (json!(["m2"]), json!([{ "id": "m2", "blobId": "blob-2" }]))
+    } else {
+        (
+            json!(["m1", "m2"]),
+            json!([{ "id": "m1", "blobId": "blob-1" }, { "id": "m2", "blobId": "blob-2" }]),
+        )
+    };
+    let responses: Vec<Value> = calls
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|call| {
+            let tag = call[2].clone();
+            match call[0].as_str().unwrap() {
+                "Mailbox/query" => json!(["Mailbox/query", { "ids": ["inbox-id"] }, tag]),
+                "Email/query" => json!(["Email/query", { "ids": ids }, tag]),
+                "Email/get" => json!(["Email/get", { "list": list }, tag]),
+                "Email/set" => json!(["Email/set", {
+                    "updated": { "m1": null },
+                    "notUpdated": { "m2": { "type": "forbidden", "description": "read-only" } }
+                }, tag]),
+                other => json!(["error", { "type": "unknownMethod", "method": other }, tag]),
+            }
+        })
+        .collect();
+    json!({ "methodResponses": responses, "sessionState": "s1" })
+}
+
+fn config(base: &str) -> JmapConfig {
+    JmapConfig {
+        session_url: format!("{}/session", base),
+        username: "user@example.com".to_string(),
+        password: "token".to_string(),
+        bearer: true,
+        timeout: Duration::from_secs(5),
+    }
+}
+
+/// 지금까지 받은 Email/set 호출
+fn set_calls(calls: &Calls) -> Vec<Value> {
+    calls
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|c| c.as_array().unwrap().clone())
+        .filter(|c| c[0] == "Email/set")
+        .collect()
+}
+
+#[tokio::test]
+async fn fetches_unseen_and_does_not_repeat_rejected_seen_updates() {
+    let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+    let base = spawn_server(calls.clone());
+    let mut source = JmapSource::new(config(&base)).unwrap();
+
+    let mails = source.fetch_new().await.unwrap();
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["m1", "m2"]);
+    assert_eq!(mails[0].subject, "First");
+    assert_eq!(mails[1].mailbox, "INBOX");
+    source.ack(&["m1".to_string(), "m2".to_string()]).await.unwrap();
+
+    let sets = set_calls(&calls);
+    assert_eq!(sets.len(), 1);
+    assert_eq!(sets[0][1]["accountId"], "acc1");
+    assert_eq!(sets[0][1]["update"]["m1"]["keywords/$seen"], true);
+    assert_eq!(sets[0][1]["update"]["m2"]["keywords/$seen"], true);
+
+    // 거부된 m2 는 서버에 안 읽음으로 남아 있어도 다시 처리하지 않음
+    assert!(source.fetch_new().await.unwrap().is_empty());
+    assert_eq!(set_calls(&calls).len(), 1);
+}
+
+#[tokio::test]
+async fn unacked_mail_comes_back_and_is_not_marked_seen() {
+    let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+    let base = spawn_server(calls.clone());
+    let mut source = JmapSource::new(config(&base)).unwrap();
+
+    // 저장 전에 실패했거나 다른 워커 몫이라 ack 하지 않음
+    assert_eq!(source.fetch_new().await.unwrap().len(), 2);
+    let mails = source.fetch_new().await.unwrap();
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["m1", "m2"]);
+    assert!(set_calls(&calls).is_empty());
+
+    // 빈 ack 는 서버에 요청하지 않음
+    source.ack(&[]).await.unwrap();
+    assert!(set_calls(&calls).is_empty());
+}
+
+#[tokio::test]
+async fn unauthorized_session_is_error() {
+    let base = spawn_server(Arc::new(Mutex::new(Vec::new())));
+    let mut cfg = config(&base);
+    cfg.password = "wrong".to_string();
+    assert!(JmapSource::new(cfg).unwrap().fetch_new().await.is_err());
+}
@@ -0,0 +1,80 @@
+// common/tests/local.rs
+//
+// 로컬 메일 원본(tests/fixtures/local) 읽기 테스트: .eml / mbox / Maildir / .eml 디렉터리
+
+#![cfg(feature = "native")]
+
+use common::local::{load_path, split_mbox, LocalFormat};
+use std::path::PathBuf;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/local").join(name)
+}
+
+fn subjects(name: &str) -> Vec<String> {
+    load_path(fixture(name))
+        .unwrap_or_else(|e| panic!("{} 읽기 실패: {}", name, e))
+        .into_iter()
+        .map(|m| m.subject)
+        .collect()
+}
+
+#[test]
+fn detect_formats() {
+    assert_eq!(LocalFormat::detect(&fixture("single.eml")).unwrap(), LocalFormat::Eml);
+    assert_eq!(LocalFormat::detect(&fixture("archive.mbox")).unwrap(), LocalFormat::Mbox);
+    assert_eq!(LocalFormat::detect(&fixture("maildir")).unwrap(), LocalFormat::Maildir);
+    assert_eq!(LocalFormat::detect(&fixture("emldir")).unwrap(), LocalFormat::EmlDir);
+}
+
+#[test]
+fn detect_short_file_is_eml() {
+    let path = std::env::temp_dir().join(format!("local-detect-{}.eml", std::process::id()));
+    std::fs::write(&path, b"Fro").unwrap();
+    let format = LocalFormat::detect(&path);
+    std::fs::remove_file(&path).ok();
+    assert_eq!(format.unwrap(), LocalFormat::Eml);
+}
+
+#[test]
+fn single_eml() {
+    let mails = load_path(fixture("single.eml")).unwrap();
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0].subject, "Single");
+    assert_eq!(mails[0].uid, "1");
+    assert!(mails[0].body.contains("한 통짜리 메일"), "{}", mails[0].body);
+    assert!(mails[0].mailbox.ends_with("single.eml"));
+}
+
+#[test]
+fn mbox_splits_and_unquotes_from_lines() {
+    let mails = load_path(fixture("archive.mbox")).unwrap();
+    assert_eq!(mails.iter().map(|m| m.subject.as_str()).collect::<Vec<_>>(), ["First", "Second"]);
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["1", "2"]);
+    assert!(mails[1].body.contains("From the quoted line"), "{}", mails[1].body);
+    assert!(!mails[1].body.contains(">From"), "{}", mails[1].body);
+}
+
+#[test]
+fn split_mbox_ignores_preamble_and_trims_separator() {
+    let raw = b"junk\nFrom a\nSubject: 1\n\nbody1\n\nFrom b\nSubject: 2\n\nbody2\n";
+    let parts = split_mbox(raw);
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0], b"Subject: 1\n\nbody1\n");
+    assert_eq!(parts[1], b"Subject: 2\n\nbody2\n");
+}
+
+#[test]
+fn maildir_reads_new_then_cur_and_skips_tmp_and_hidden() {
+    assert_eq!(subjects("maildir"), ["Maildir new", "Maildir cur"]);
+}
+
+#[test]
+fn eml_dir_reads_only_eml_files() {
+    assert_eq!(subjects("emldir"), ["Dir A", "Dir B"]);
+}
+
+#[test]
+fn missing_path_is_error() {
+    assert!(load_path(fixture("does-not-exist")).is_err());
+}
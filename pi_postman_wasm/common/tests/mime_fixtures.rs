@@ -0,0 +1,85 @@
+// common/tests/mime_fixtures.rs
+//
+// 까다로운 메일 모음(tests/fixtures/mime) 파싱 회귀 테스트
+
+#![cfg(feature = "native")]
+
+use common::gmail::{parse_message, ParsedEmail};
+use common::mime::{decode_base64_lenient, decode_header, unfold};
+
+fn fixture_bytes(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/mime/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{} 읽기 실패: {}", path, e))
+}
+
+fn fixture(name: &str) -> ParsedEmail {
+    parse_message(1, &fixture_bytes(name))
+}
+
+#[test]
+fn unfold_removes_only_folding_line_breaks() {
+    assert_eq!(unfold(b"a\r\n b\r\n\tc"), b"a b\tc");
+    assert_eq!(unfold(b"a\n b"), b"a b");
+    assert_eq!(unfold(b"a\r\nb"), b"a\r\nb");
+}
+
+#[test]
+fn folded_encoded_words_join_split_multibyte_chars() {
+    let d = decode_header(b"=?UTF-8?B?7JWI64WV7ZU=?=\r\n =?UTF-8?B?mOyEuOyalCDtmozsnZjroZ0=?=");
+    assert_eq!(d.text, "안녕하세요 회의록");
+}
+
+#[test]
+fn folded_headers() {
+    let email = fixture("folded_headers.eml");
+    assert_eq!(email.subject, "안녕하세요 회의록");
+    assert_eq!(email.from, "홍길동 <hong@example.com>");
+    let from = email.headers.from.expect("From 주소");
+    assert_eq!(from.address, "hong@example.com");
+    assert_eq!(email.headers.references, ["a@example.com", "b@example.com", "c@example.com"]);
+    for (name, value) in &email.headers.raw {
+        assert!(!value.contains('\r') && !value.contains('\n'), "{} 값에 줄바꿈 남음: {:?}", name, value);
+    }
+}
+
+#[test]
+fn unlabeled_euc_kr() {
+    let email = fixture("euc_kr_unlabeled.eml");
+    assert_eq!(email.subject, "주문 확인");
+    assert!(email.body.contains("발송되었습니다"), "{}", email.body);
+    assert!(!email.parse_warnings.is_empty());
+}
+
+#[test]
+fn iso_2022_kr_body_and_ks_c_5601_subject() {
+    let email = fixture("iso_2022_kr.eml");
+    assert_eq!(email.subject, "공지");
+    assert!(email.body.contains("공지사항 입니다"), "{}", email.body);
+}
+
+#[test]
+fn unclosed_encoded_word() {
+    let email = fixture("unclosed_encoded_word.eml");
+    assert_eq!(email.subject, "안내");
+    assert!(email.parse_warnings.iter().any(|w| w.contains("닫히지 않은")), "{:?}", email.parse_warnings);
+}
+
+#[test]
+fn declared_charset_mismatch() {
+    let email = fixture("declared_mismatch.eml");
+    assert!(email.body.contains("실제로는 UTF-8 입니다"), "{}", email.body);
+    assert!(email.parse_warnings.iter().any(|w| w.contains("불일치")), "{:?}", email.parse_warnings);
+}
+
+#[test]
+fn base64_keeps_padded_tail() {
+    assert_eq!(decode_base64_lenient(b"QQ=="), b"A");
+    assert_eq!(decode_base64_lenient(b"QUI="), b"AB");
+    assert_eq!(decode_base64_lenient(b"QUJD"), b"ABC");
+    // 부분 fetch 로 잘린 1글자 꼬리는 버림
+    assert_eq!(decode_base64_lenient(b"QUJDR"), b"ABC");
+
+    let raw = fixture_bytes("base64_padded.eml");
+    let start = raw.windows(4).position(|w| w == b"\r\n\r\n").expect("본문 구분") + 4;
+    assert_eq!(String::from_utf8(decode_base64_lenient(&raw[start..])).unwrap(), "끝 글자까지 AB");
+}
@@ -0,0 +1,166 @@
+// common/tests/pop3.rs
+//
+// 대본대로 응답하는 TCP POP3 서버를 띄워 Pop3Source 확인
+
+#![cfg(feature = "native")]
+
+use common::pop3::{Pop3Config, Pop3Source};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const FIRST: &str = "Subject: First\r\n\r\nfirst body\r\n";
+/// 점으로 시작하는 줄은 서버가 점을 하나 더 붙여 보냄 (점 스터핑)
+const SECOND: &str = "Subject: Second\r\n\r\n.hidden dot line\r\nsecond body\r\n";
+
+/// 메시지 번호 → (UIDL, 원문)
+type Mailbox = Arc<Mutex<Vec<(String, String)>>>;
+
+/// 받은 명령을 기록하는 POP3 대역 서버 (연결마다 스레드 하나)
+fn spawn_server(mailbox: Mailbox, log: Arc<Mutex<Vec<String>>>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (mailbox, log) = (mailbox.clone(), log.clone());
+            thread::spawn(move || serve(stream, mailbox, log));
+        }
+    });
+    port
+}
+
+fn serve(stream: TcpStream, mailbox: Mailbox, log: Arc<Mutex<Vec<String>>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    writer.write_all(b"+OK POP3 ready\r\n").unwrap();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let cmd = line.trim_end().to_string();
+        log.lock().unwrap().push(cmd.clone());
+        let reply = match cmd.split_once(' ').unwrap_or((cmd.as_str(), "")) {
+            ("USER", _) => "+OK\r\n".to_string(),
+            ("PASS", "secret") => "+OK logged in\r\n".to_string(),
+            ("PASS", _) => "-ERR invalid password\r\n".to_string(),
+            ("UIDL", _) => {
+                let mut out = "+OK\r\n".to_string();
+                for (i, (uid, _)) in mailbox.lock().unwrap().iter().enumerate() {
+                    out.push_str(&format!("{} {}\r\n", i + 1, uid));
+                }
+                out + ".\r\n"
+            }
+            ("RETR", n) => match n.parse::<usize>().ok().and_then(|n| mailbox.lock().unwrap().get(n - 1).cloned()) {
+                Some((_, raw)) => {
+                    let mut out = "+OK\r\n".to_string();
+                    for l in raw.lines() {
+                        if l.starts_with('.') {
+                            out.push('.');
+                        }
+                        out.push_str(l);
+                        out.push_str("\r\n");
+                    }
+                    out + ".\r\n"
+                }
+                None => "-ERR no such message\r\n".to_string(),
+            },
+            ("QUIT", _) => {
+                writer.write_all(b"+OK bye\r\n").unwrap();
+                return;
+            }
+            _ => "-ERR unknown command\r\n".to_string(),
+        };
+        writer.write_all(reply.as_bytes()).unwrap();
+    }
+}
+
+fn config(port: u16, password: &str, seen_file: Option<std::path::PathBuf>) -> Pop3Config {
+    Pop3Config {
+        host: "127.0.0.1".to_string(),
+        port,
+        tls: false,
+        username: "user@example.com".to_string(),
+        password: password.to_string(),
+        seen_file,
+        timeout: Duration::from_secs(5),
+    }
+}
+
+#[tokio::test]
+async fn fetches_acked_mail_once_and_persists_uidl() {
+    let mailbox: Mailbox = Arc::new(Mutex::new(vec![
+        ("uid-a".to_string(), FIRST.to_string()),
+        ("uid-b".to_string(), SECOND.to_string()),
+    ]));
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let port = spawn_server(mailbox.clone(), log.clone());
+    let seen_file = std::env::temp_dir().join(format!("pop3-seen-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&seen_file);
+
+    let mut source = Pop3Source::new(config(port, "secret", Some(seen_file.clone())));
+    let mails = source.fetch_new().await.unwrap();
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["uid-a", "uid-b"]);
+    assert_eq!(mails[0].subject, "First");
+    assert!(mails[1].body.contains(".hidden dot line"), "{}", mails[1].body);
+    assert!(!mails[1].body.contains("..hidden"), "{}", mails[1].body);
+    assert_eq!(mails[0].mailbox, "INBOX");
+    source.ack(&["uid-a".to_string(), "uid-b".to_string()]);
+
+    // 같은 소스로 다시 확인하면 새 메일 없음
+    assert!(source.fetch_new().await.unwrap().is_empty());
+
+    // 재시작해도 기록 파일로 중복 방지, 새로 온 메일만 가져옴
+    mailbox.lock().unwrap().push(("uid-c".to_string(), "Subject: Third\r\n\r\nthird\r\n".to_string()));
+    let mut restarted = Pop3Source::new(config(port, "secret", Some(seen_file.clone())));
+    let mails = restarted.fetch_new().await.unwrap();
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["uid-c"]);
+    restarted.ack(&["uid-c".to_string()]);
+
+    // 서버에서 지워진 UIDL 은 기록에서도 정리
+    mailbox.lock().unwrap().remove(0);
+    assert!(restarted.fetch_new().await.unwrap().is_empty());
+    let seen = std::fs::read_to_string(&seen_file).unwrap();
+    std::fs::remove_file(&seen_file).ok();
+    assert_eq!(seen.lines().collect::<Vec<_>>(), ["uid-b", "uid-c"]);
+
+    let log = log.lock().unwrap();
+    assert!(log.iter().any(|c| c == "RETR 3"), "{:?}", log);
+    assert_eq!(log.iter().filter(|c| c.starts_with("RETR")).count(), 3, "{:?}", log);
+    assert!(log.iter().any(|c| c == "QUIT"), "{:?}", log);
+}
+
+#[tokio::test]
+async fn unacked_mail_comes_back_after_restart() {
+    let mailbox: Mailbox = Arc::new(Mutex::new(vec![
+        ("uid-a".to_string(), FIRST.to_string()),
+        ("uid-b".to_string(), SECOND.to_string()),
+    ]));
+    let port = spawn_server(mailbox, Arc::new(Mutex::new(Vec::new())));
+    let seen_file = std::env::temp_dir().join(format!("pop3-unacked-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&seen_file);
+
+    // uid-b 는 저장 전에 실패했다고 보고 ack 하지 않음
+    let mut source = Pop3Source::new(config(port, "secret", Some(seen_file.clone())));
+    assert_eq!(source.fetch_new().await.unwrap().len(), 2);
+    source.ack(&["uid-a".to_string()]);
+    let mails = source.fetch_new().await.unwrap();
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["uid-b"]);
+
+    // 기록 파일에도 남지 않아 재시작 후에도 다시 옴
+    let mut restarted = Pop3Source::new(config(port, "secret", Some(seen_file.clone())));
+    let mails = restarted.fetch_new().await.unwrap();
+    std::fs::remove_file(&seen_file).ok();
+    assert_eq!(mails.iter().map(|m| m.uid.as_str()).collect::<Vec<_>>(), ["uid-b"]);
+}
+
+#[tokio::test]
+async fn login_failure_is_error() {
+    let mailbox: Mailbox = Arc::new(Mutex::new(Vec::new()));
+    let port = spawn_server(mailbox, Arc::new(Mutex::new(Vec::new())));
+    let err = Pop3Source::new(config(port, "wrong", None)).fetch_new().await.unwrap_err();
+    assert!(err.to_string().contains("로그인 실패"), "{}", err);
+}
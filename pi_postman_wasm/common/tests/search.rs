@@ -0,0 +1,119 @@
+// common/tests/search.rs
+//
+// 검색 질의 파싱과 SQLite / 메모리 저장소 검색 확인
+
+#![cfg(feature = "native")]
+
+use common::email::Email;
+use common::search::{parse_date, SearchQuery};
+use common::sqlite::SqliteStore;
+use common::store::{EmailStore, MemoryStore};
+
+#[test]
+fn parses_terms_phrases_and_filters() {
+    let q = SearchQuery::parse(
+        r#"청구서 "결제 완료" from:bank Category:finance after:2024-01-01 before:"2024-02-01""#,
+    )
+    .unwrap();
+    assert_eq!(q.terms, ["청구서"]);
+    assert_eq!(q.phrases, ["결제 완료"]);
+    assert_eq!(q.from.as_deref(), Some("bank"));
+    assert_eq!(q.category.as_deref(), Some("finance"));
+    assert_eq!(q.after, Some(parse_date("2024-01-01").unwrap()));
+    assert_eq!(q.before, Some(parse_date("2024-02-01").unwrap()));
+    assert!(q.has_text());
+
+    // 따옴표 필터 값에는 공백이 들어갈 수 있음
+    let q = SearchQuery::parse(r#"from:"Bank Support""#).unwrap();
+    assert_eq!(q.from.as_deref(), Some("Bank Support"));
+    assert!(!q.has_text());
+
+    // 필터가 아닌 콜론 단어와 빈 값은 그대로 / 무시
+    let q = SearchQuery::parse(r#"http://example.com from: """#).unwrap();
+    assert_eq!(q.terms, ["http://example.com"]);
+    assert!(q.phrases.is_empty() && q.from.is_none());
+}
+
+#[test]
+fn rejects_unknown_filters_and_bad_dates() {
+    let err = SearchQuery::parse(r#"foo:"bar""#).unwrap_err();
+    assert!(err.contains("foo"), "{}", err);
+    assert!(SearchQuery::parse("after:yesterday").is_err());
+    assert!(SearchQuery::parse("before:2024-13-01").is_err());
+    assert!(SearchQuery::parse("after:2024-01-01T09:00:00+09:00").is_ok());
+}
+
+fn sample() -> Vec<Email> {
+    let mut bill = Email::new("Bank <bank@example.com>", "me@example.com", "청구서 안내", "이번 달 결제 완료 되었습니다. invoice attached");
+    bill.category = Some("finance".into());
+    let lunch = Email::new("friend@example.com", "me@example.com", "hello", "lunch tomorrow?");
+    let html = Email::new(
+        "evil@example.com",
+        "me@example.com",
+        "alert",
+        "<script>alert(1)</script> invoice <img src=x onerror=alert(2)>",
+    );
+    vec![bill, lunch, html]
+}
+
+fn subjects(store: &dyn EmailStore, query: &str) -> Vec<String> {
+    let page = store.search(&SearchQuery::parse(query).unwrap(), 10, 0).unwrap();
+    assert_eq!(page.total, page.hits.len());
+    let mut subjects: Vec<String> = page.hits.into_iter().map(|h| h.subject).collect();
+    subjects.sort();
+    subjects
+}
+
+fn check_store(store: &dyn EmailStore) {
+    for email in sample() {
+        store.insert(&email).unwrap();
+    }
+    assert_eq!(subjects(store, "invoice"), ["alert", "청구서 안내"]);
+    assert_eq!(subjects(store, "inv from:bank"), ["청구서 안내"]);
+    assert_eq!(subjects(store, r#""결제 완료" category:FINANCE"#), ["청구서 안내"]);
+    assert_eq!(subjects(store, r#"from:"friend@""#), ["hello"]);
+    assert_eq!(subjects(store, "lunch before:2000-01-01"), Vec::<String>::new());
+    assert_eq!(subjects(store, "after:2000-01-01").len(), 3);
+
+    // 본문 HTML 은 이스케이프되고 일치 부분만 <mark>
+    let page = store.search(&SearchQuery::parse("invoice from:evil").unwrap(), 10, 0).unwrap();
+    let snippet = &page.hits[0].snippet;
+    assert!(snippet.contains("<mark>invoice</mark>"), "{}", snippet);
+    assert!(snippet.contains("&lt;script&gt;"), "{}", snippet);
+    assert!(!snippet.contains("<script") && !snippet.contains("<img"), "{}", snippet);
+}
+
+#[test]
+fn memory_search() {
+    check_store(&MemoryStore::default());
+}
+
+#[test]
+fn sqlite_search() {
+    check_store(&SqliteStore::open_in_memory().unwrap());
+}
+
+#[test]
+fn sqlite_search_survives_vacuum() {
+    let path = std::env::temp_dir().join(format!("search-vacuum-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let emails = sample();
+    {
+        let store = SqliteStore::open(&path).unwrap();
+        for email in &emails {
+            store.insert(email).unwrap();
+        }
+        // 앞쪽 행을 지워 rowid 에 빈 자리를 만든 뒤 VACUUM
+        store.delete(&emails[0].id).unwrap();
+    }
+    rusqlite::Connection::open(&path).unwrap().execute_batch("VACUUM").unwrap();
+
+    let store = SqliteStore::open(&path).unwrap();
+    let page = store.search(&SearchQuery::parse("lunch").unwrap(), 10, 0).unwrap();
+    let found: Vec<&str> = page.hits.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(found, [emails[1].id.as_str()]);
+    let page = store.search(&SearchQuery::parse("script").unwrap(), 10, 0).unwrap();
+    assert_eq!(page.hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), [emails[2].id.as_str()]);
+    drop(store);
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,61 @@
+// common/tests/store.rs
+//
+// EmailStore::modify 원자성 테스트 (동시 수정이 서로를 덮어쓰지 않는지)
+
+#![cfg(feature = "native")]
+
+use common::email::Email;
+use common::sqlite::SqliteStore;
+use common::store::{EmailStore, MemoryStore};
+use std::sync::Arc;
+use std::thread;
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 25;
+
+/// 여러 스레드가 같은 메일 본문에 한 글자씩 덧붙임
+fn concurrent_appends(store: Arc<dyn EmailStore>) {
+    let email = Email::new("a@example.com", "b@example.com", "제목", "");
+    store.insert(&email).unwrap();
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let (store, id) = (store.clone(), email.id.clone());
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    store.modify(&id, Box::new(|e| e.body.push('x'))).unwrap();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    assert_eq!(store.get(&email.id).unwrap().unwrap().body.len(), THREADS * ROUNDS);
+}
+
+#[test]
+fn memory_modify_is_atomic() {
+    concurrent_appends(Arc::new(MemoryStore::default()));
+}
+
+#[test]
+fn sqlite_modify_is_atomic() {
+    let path = std::env::temp_dir().join(format!("store-modify-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    concurrent_appends(Arc::new(SqliteStore::open(&path).unwrap()));
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn modify_returns_updated_email_and_errors_when_missing() {
+    let store = MemoryStore::default();
+    let email = Email::new("a@example.com", "b@example.com", "제목", "본문");
+    store.insert(&email).unwrap();
+    let updated = store
+        .modify(&email.id, Box::new(|e| e.category = Some("work".into())))
+        .unwrap();
+    assert_eq!(updated.category.as_deref(), Some("work"));
+    assert_eq!(store.get(&email.id).unwrap().unwrap().category.as_deref(), Some("work"));
+    assert!(store.modify("missing", Box::new(|_| {})).is_err());
+}
@@ -0,0 +1,242 @@
+// master/src/accounts.rs
+
+use anyhow::{anyhow, Context, Result};
+use common::gmail::{GmailConfig, ImapTimeouts};
+use common::jmap::JmapConfig;
+use common::pop3::Pop3Config;
+use common::source::MailSource;
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+/// 모니터링할 메일 계정 하나
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountConfig {
+    /// 로그/API 에 표시할 계정 이름
+    pub name: String,
+    pub email: String,
+    /// 앱 비밀번호. "env:VAR_NAME" 형식이면 환경 변수에서 읽음
+    pub password: String,
+    /// 확인할 메일함 목록 (기본값: INBOX)
+    #[serde(default = "default_folders")]
+    pub folders: Vec<FolderConfig>,
+    /// 계정 기본 Discord 웹훅 (없으면 DISCORD_WEBHOOK_URL)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// 분류 카테고리 → 웹훅 URL (일치하면 기본 웹훅 대신 사용)
+    #[serde(default)]
+    pub routes: HashMap<String, String>,
+    /// 메일 소스 종류 (기본값: Gmail IMAP)
+    #[serde(default)]
+    pub source: SourceKind,
+}
+
+/// 계정의 메일 소스
+///
+/// `{"type": "pop3", "host": "pop.example.com"}` 나
+/// `{"type": "jmap", "session_url": "https://api.fastmail.com/jmap/session", "bearer": true}`
+/// 처럼 지정합니다. 로그인에는 계정의 email / password 를 사용합니다.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceKind {
+    #[default]
+    Imap,
+    Pop3 {
+        host: String,
+        /// 기본값: TLS 995, 평문 110
+        #[serde(default)]
+        port: Option<u16>,
+        /// true 면 TLS 없이 접속 (로컬 테스트 서버용)
+        #[serde(default)]
+        plain: bool,
+        /// 처리한 UIDL 기록 파일
+        #[serde(default)]
+        seen_file: Option<String>,
+    },
+    Jmap {
+        session_url: String,
+        /// true 면 password 를 API 토큰(Bearer)으로 사용
+        #[serde(default)]
+        bearer: bool,
+    },
+}
+
+fn default_folders() -> Vec<FolderConfig> {
+    vec![FolderConfig::from("INBOX".to_string())]
+}
+
+/// 메일함별 처리 방식
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderRule {
+    /// 분류 + 라벨 + 알림
+    #[default]
+    Process,
+    /// 스팸함 감사: 분류 결과가 SPAM 이 아닐 때만 오탐 의심 알림
+    Audit,
+    /// 분류 + 라벨만, 알림 없음
+    Silent,
+    /// 가져오기만 하고 분류하지 않음 (읽음 처리만)
+    Ignore,
+}
+
+/// 모니터링할 메일함 하나
+///
+/// JSON 에서는 `"INBOX"` 처럼 문자열만 써도 되고,
+/// `{"mailbox": "[Gmail]/Spam", "rule": "audit"}` 나
+/// `{"category": "promotions", "rule": "ignore"}` 처럼 상세 지정도 가능합니다.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(from = "FolderEntry")]
+pub struct FolderConfig {
+    pub mailbox: String,
+    /// Gmail 카테고리 탭 (primary, social, promotions, updates, forums)
+    pub category: Option<String>,
+    /// 추가 Gmail 검색식 (X-GM-RAW)
+    pub gmail_raw: Option<String>,
+    pub rule: FolderRule,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FolderEntry {
+    Name(String),
+    Detailed {
+        #[serde(default = "inbox")]
+        mailbox: String,
+        #[serde(default)]
+        category: Option<String>,
+        #[serde(default)]
+        gmail_raw: Option<String>,
+        #[serde(default)]
+        rule: FolderRule,
+    },
+}
+
+fn inbox() -> String {
+    "INBOX".to_string()
+}
+
+impl From<String> for FolderConfig {
+    fn from(mailbox: String) -> Self {
+        FolderConfig { mailbox, category: None, gmail_raw: None, rule: FolderRule::Process }
+    }
+}
+
+impl From<FolderEntry> for FolderConfig {
+    fn from(entry: FolderEntry) -> Self {
+        match entry {
+            FolderEntry::Name(name) => FolderConfig::from(name),
+            FolderEntry::Detailed { mailbox, category, gmail_raw, rule } => {
+                FolderConfig { mailbox, category, gmail_raw, rule }
+            }
+        }
+    }
+}
+
+impl FolderConfig {
+    /// IMAP 검색에 붙일 X-GM-RAW 식 (카테고리 + 추가 검색식)
+    pub fn gmail_query(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(cat) = self.category.as_deref().filter(|c| !c.trim().is_empty()) {
+            parts.push(format!("category:{}", cat.trim().to_lowercase()));
+        }
+        if let Some(raw) = self.gmail_raw.as_deref().filter(|r| !r.trim().is_empty()) {
+            parts.push(raw.trim().to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// 로그/상태 표시용 이름 (예: "INBOX[category:promotions]")
+    pub fn display_name(&self) -> String {
+        match self.gmail_query() {
+            Some(q) => format!("{}[{}]", self.mailbox, q),
+            None => self.mailbox.clone(),
+        }
+    }
+}
+
+impl AccountConfig {
+    pub fn gmail(&self) -> GmailConfig {
+        GmailConfig {
+            email: self.email.clone(),
+            password: self.password.clone(),
+        }
+    }
+
+    /// IMAP 이 아닌 소스 (IMAP 은 폴더별 처리를 위해 notifier 가 직접 다룸)
+    pub fn mail_source(&self) -> Result<Option<MailSource>> {
+        let timeout = ImapTimeouts::from_env().read;
+        match &self.source {
+            SourceKind::Imap => Ok(None),
+            SourceKind::Pop3 { host, port, plain, seen_file } => Ok(Some(MailSource::pop3(Pop3Config {
+                host: host.clone(),
+                port: port.unwrap_or(if *plain { 110 } else { 995 }),
+                tls: !plain,
+                username: self.email.clone(),
+                password: self.password.clone(),
+                seen_file: seen_file.as_ref().map(PathBuf::from),
+                timeout,
+            }))),
+            SourceKind::Jmap { session_url, bearer } => MailSource::jmap(JmapConfig {
+                session_url: session_url.clone(),
+                username: self.email.clone(),
+                password: self.password.clone(),
+                bearer: *bearer,
+                timeout,
+            })
+            .map(Some),
+        }
+    }
+
+    /// 카테고리에 맞는 웹훅: routes → 계정 기본 → 전역 기본 순
+    pub fn webhook_for<'a>(&'a self, category: &str, fallback: Option<&'a str>) -> Option<&'a str> {
+        self.routes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(category.trim()))
+            .map(|(_, v)| v.as_str())
+            .or(self.webhook_url.as_deref())
+            .or(fallback)
+    }
+}
+
+/// 계정 목록 읽기
+///
+/// NOTIFIER_ACCOUNTS_FILE(JSON 배열)이 있으면 사용하고,
+/// 없으면 GMAIL_EMAIL / GMAIL_PASSWORD 로 단일 계정("default")을 구성합니다.
+pub fn load_accounts() -> Result<Vec<AccountConfig>> {
+    let Ok(path) = env::var("NOTIFIER_ACCOUNTS_FILE") else {
+        return Ok(vec![AccountConfig {
+            name: "default".to_string(),
+            email: env::var("GMAIL_EMAIL").map_err(|_| anyhow!("GMAIL_EMAIL 필요"))?,
+            password: env::var("GMAIL_PASSWORD").map_err(|_| anyhow!("GMAIL_PASSWORD 필요"))?,
+            folders: default_folders(),
+            webhook_url: None,
+            routes: HashMap::new(),
+            source: SourceKind::Imap,
+        }]);
+    };
+
+    let raw = fs::read_to_string(&path).with_context(|| format!("계정 파일 읽기 실패: {}", path))?;
+    let mut accounts: Vec<AccountConfig> =
+        serde_json::from_str(&raw).with_context(|| format!("계정 파일 파싱 실패: {}", path))?;
+    if accounts.is_empty() {
+        return Err(anyhow!("계정 파일에 계정이 없습니다: {}", path));
+    }
+
+    for acc in &mut accounts {
+        if let Some(var) = acc.password.strip_prefix("env:") {
+            acc.password = env::var(var)
+                .map_err(|_| anyhow!("[{}] 비밀번호 환경 변수 {} 없음", acc.name, var))?;
+        }
+        if acc.folders.is_empty() {
+            acc.folders = default_folders();
+        }
+    }
+
+    let mut names: Vec<&str> = accounts.iter().map(|a| a.name.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    if names.len() != accounts.len() {
+        return Err(anyhow!("계정 이름이 중복되었습니다"));
+    }
+    Ok(accounts)
+}
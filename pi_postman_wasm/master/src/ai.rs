@@ -1,11 +1,50 @@
 //master/src/ai.rs
 
-use anyhow::Result;
+use crate::session::AiProvider;
+use anyhow::{anyhow, Context, Result};
 use common::email::Email;
-use common::classifier::classify_via_openai;
+use common::classifier::{classify_via_openai, parse_classification, user_prompt, SYSTEM_PROMPT};
+use serde_json::{json, Value};
+
+/// 분류에 보낼 본문 (헤더 정보가 있으면 앞에 요약을 붙여 분류 정확도를 높임)
+pub fn prompt_body(email: &Email) -> String {
+    let summary = email.headers.summary();
+    if summary.is_empty() {
+        return email.body.clone();
+    }
+    format!("{}\n\n{}", summary, email.body)
+}
 
 /// 이메일 객체를 AI에 보내 분류 결과 반환
 pub async fn classify_with_ai(email: &Email) -> Result<(String, f32)> {
-    // common::classifier 내부 로직 사용
-    classify_via_openai(&email.subject, &email.body).await
+    classify_via_openai(&email.subject, &prompt_body(email)).await
+}
+
+/// 주어진 제공자(AI 세션 등)로 분류 (타임아웃은 호출하는 쪽에서)
+///
+/// 세션 클라이언트가 검사한 주소에 고정되어 있어야 하므로 chat/completions 를 직접 호출합니다.
+pub async fn classify_with_provider(provider: &AiProvider, email: &Email) -> Result<(String, f32)> {
+    let config = &provider.config;
+    let body = json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": SYSTEM_PROMPT },
+            { "role": "user", "content": user_prompt(&email.subject, &prompt_body(email)) },
+        ],
+    });
+    let resp = provider
+        .http
+        .post(format!("{}/chat/completions", config.base()))
+        .bearer_auth(&config.api_key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| anyhow!("AI 호출 실패: {}", e))?;
+    let status = resp.status();
+    let text = resp.text().await?;
+    if !status.is_success() {
+        return Err(anyhow!("AI 응답 {}: {}", status, text));
+    }
+    let v: Value = serde_json::from_str(&text).context("AI 응답 JSON 파싱 실패")?;
+    parse_classification(v["choices"][0]["message"]["content"].as_str().unwrap_or_default())
 }
\ No newline at end of file
@@ -1,56 +1,423 @@
 //master/src/api.rs
 
-use axum::{routing::post, Router, Json};
-use serde::{Deserialize, Serialize};
-use common::email::{process_incoming_email, get_email};
-use crate::ai::classify_with_ai;
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware,
+    response::IntoResponse,
+    routing::{get, post},
+    Router, Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use common::email::{process_incoming_email, update_email, Email};
+use common::search::{parse_date, SearchHit, SearchQuery};
+use common::store::{store, Cursor, ListFilter, SortOrder};
+use crate::ai::classify_with_provider;
+use crate::auth::{self, require_auth};
+use crate::batch::{self, BATCH_MAX_BYTES};
+use crate::config::env_flag;
+use crate::email::{raw_message, reparse_email};
+use crate::error::{request_id, ApiError, ApiJson, ApiPath, ApiQuery, ApiResult};
+use crate::events;
+use crate::inbound::{self, INBOUND_MAX_BYTES};
+use crate::jobs::{self, Job};
+use crate::query;
+use crate::session::{self, SessionInfo};
+use crate::status::{snapshot, AccountStatus};
+use std::env;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::warn;
 
 #[derive(Deserialize)]
-pub struct EmailReceiveRequest { pub from: String, pub to: String, pub subject: String, pub body: String }
+pub struct EmailReceiveRequest {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    /// 수신 후 분류 작업 등록 (없으면 AUTO_CLASSIFY 환경 변수)
+    #[serde(default)]
+    pub classify: Option<bool>,
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// 분류에 사용할 AI 세션 (없으면 서버 환경 변수 설정)
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct EmailReceiveResponse {
+    pub success: bool,
+    pub email_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct ClassifyEmailRequest {
+    pub email_id: String,
+    /// 완료 시 작업 결과를 POST 받을 URL
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// 분류에 사용할 AI 세션 (없으면 서버 환경 변수 설정)
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AiConnectRequest {
+    pub api_key: String,
+    pub model: String,
+    /// OpenAI 호환 API 주소 (기본 https://api.openai.com/v1)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// 호출 타임아웃 (초, 기본 60)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
 
 #[derive(Serialize)]
-pub struct EmailReceiveResponse { pub success: bool, pub email_id: Option<String>, pub message: String }
+pub struct AiConnectResponse {
+    pub success: bool,
+    pub session_id: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub message: String,
+}
 
 #[derive(Deserialize)]
-pub struct ClassifyEmailRequest { pub email_id: String }
+pub struct SearchParams {
+    pub q: String,
+    #[serde(default = "first_page")]
+    pub page: usize,
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+}
+
+fn first_page() -> usize { 1 }
+fn default_per_page() -> usize { 20 }
 
 #[derive(Serialize)]
-pub struct ClassifyEmailResponse { pub success: bool, pub category: Option<String>, pub confidence: Option<f32>, pub message: String }
+pub struct SearchResponse { pub query: String, pub total: usize, pub page: usize, pub per_page: usize, pub hits: Vec<SearchHit> }
 
 #[derive(Deserialize)]
-pub struct AiConnectRequest { pub api_key: String, pub model: String }
+pub struct ListParams {
+    pub category: Option<String>,
+    pub from: Option<String>,
+    /// YYYY-MM-DD 또는 RFC 3339 (포함)
+    pub since: Option<String>,
+    /// YYYY-MM-DD 또는 RFC 3339 (미포함)
+    pub until: Option<String>,
+    pub processed: Option<bool>,
+    /// newest (기본) | oldest
+    pub order: Option<String>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
 
+/// 목록용 요약 (본문 제외)
 #[derive(Serialize)]
-pub struct AiConnectResponse { pub success: bool, pub session_id: Option<String>, pub message: String }
+pub struct EmailSummary {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub received_at: DateTime<Utc>,
+    pub category: Option<String>,
+    pub ai_processed: bool,
+}
+
+impl From<Email> for EmailSummary {
+    fn from(e: Email) -> Self {
+        EmailSummary { id: e.id, from: e.from, to: e.to, subject: e.subject, received_at: e.received_at, category: e.category, ai_processed: e.ai_processed }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EmailListResponse { pub items: Vec<EmailSummary>, pub next_cursor: Option<String> }
+
+#[derive(Deserialize)]
+pub struct EmailPatchRequest {
+    /// 없으면 그대로, null 이면 분류 해제, 문자열이면 수동 지정
+    #[serde(default, deserialize_with = "present")]
+    pub category: Option<Option<String>>,
+}
+
+/// 필드가 있으면 Some(값) — null 과 누락을 구분하기 위함
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(d: D) -> Result<Option<T>, D::Error> {
+    T::deserialize(d).map(Some)
+}
+
+const DEFAULT_LIST_LIMIT: usize = 50;
+const MAX_LIST_LIMIT: usize = 200;
+
+/// CORS 설정 (환경 변수)
+///   CORS_ALLOWED_ORIGINS  "*" 또는 쉼표로 구분한 origin 목록 (없으면 교차 출처 허용 안 함)
+///   CORS_ALLOWED_METHODS  쉼표로 구분 (기본 GET,POST,PATCH,DELETE)
+///   CORS_MAX_AGE          preflight 캐시 초 (기본 600)
+pub fn cors_layer() -> CorsLayer {
+    let list = |key: &str, default: &str| -> Vec<String> {
+        env::var(key)
+            .unwrap_or_else(|_| default.to_string())
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+    let origins = list("CORS_ALLOWED_ORIGINS", "");
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|o| {
+            HeaderValue::from_str(o).map_err(|_| warn!("[API] 잘못된 CORS origin 무시: {}", o)).ok()
+        }))
+    };
+    let methods: Vec<Method> = list("CORS_ALLOWED_METHODS", "GET,POST,PATCH,DELETE")
+        .iter()
+        .filter_map(|m| Method::from_bytes(m.to_ascii_uppercase().as_bytes()).ok())
+        .collect();
+    let max_age = env::var("CORS_MAX_AGE").ok().and_then(|s| s.parse().ok()).unwrap_or(600);
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(methods)
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            HeaderName::from_static("x-api-key"),
+            HeaderName::from_static("x-request-id"),
+            HeaderName::from_static("last-event-id"),
+        ])
+        .expose_headers([header::LOCATION, header::RETRY_AFTER, HeaderName::from_static("x-request-id")])
+        .max_age(Duration::from_secs(max_age))
+}
 
 pub fn create_router() -> Router {
+    if auth::auth_disabled() {
+        warn!("[API] API_AUTH=disabled — 인증 없이 모든 요청을 허용함 (개발용)");
+    }
     Router::new()
         .route("/api/email/receive", post(receive_email))
         .route("/api/email/classify", post(classify_email))
+        .route("/api/email/receive/batch", post(batch::receive_batch).layer(DefaultBodyLimit::max(BATCH_MAX_BYTES)))
+        .route("/api/email/classify/batch", post(batch::classify_batch).layer(DefaultBodyLimit::max(BATCH_MAX_BYTES)))
+        .route("/api/batches/:id", get(batch::get_batch))
+        .route("/api/jobs/:id", get(get_job))
         .route("/api/ai/connect", post(connect_ai))
+        .route("/api/ai/query", post(query::query_ai))
+        .route("/api/ai/session/:id", get(get_ai_session).delete(disconnect_ai))
+        .route("/api/accounts", get(list_accounts))
+        .route("/api/search", get(search_emails))
+        .route("/api/events", get(events::sse))
+        .route("/api/events/ws", get(events::ws))
+        .route("/api/emails", get(list_emails))
+        .route("/api/emails/:id", get(get_email_by_id).delete(delete_email).patch(patch_email))
+        .route("/api/emails/:id/raw", get(export_raw))
+        .route("/api/emails/:id/reparse", post(reparse))
+        // 메일 서비스 inbound 웹훅 (첨부 때문에 본문 크기 제한을 늘림)
+        .route("/api/inbound/mailgun", post(inbound::mailgun).layer(DefaultBodyLimit::max(INBOUND_MAX_BYTES)))
+        .route("/api/inbound/sendgrid", post(inbound::sendgrid).layer(DefaultBodyLimit::max(INBOUND_MAX_BYTES)))
+        .route("/api/inbound/postmark", post(inbound::postmark).layer(DefaultBodyLimit::max(INBOUND_MAX_BYTES)))
+        .fallback(not_found)
+        // 바깥부터: 요청 ID → CORS (preflight 는 인증 전에 응답) → 인증
+        .layer(middleware::from_fn(require_auth))
+        .layer(cors_layer())
+        .layer(middleware::from_fn(request_id))
+}
+
+/// 요청에 classify 가 없으면 AUTO_CLASSIFY 환경 변수를 따름
+pub fn wants_classify(flag: Option<bool>) -> bool {
+    flag.unwrap_or_else(|| env_flag("AUTO_CLASSIFY"))
 }
 
 // 수신
-async fn receive_email(Json(payload): Json<EmailReceiveRequest>) -> Json<EmailReceiveResponse> {
-    match process_incoming_email(&payload.from, &payload.to, &payload.subject, &payload.body).await {
-        Ok(id) => Json(EmailReceiveResponse { success: true, email_id: Some(id), message: "이메일 수신 성공".into() }),
-        Err(e) => Json(EmailReceiveResponse { success: false, email_id: None, message: format!("이메일 처리 실패: {}", e) }),
+async fn receive_email(ApiJson(payload): ApiJson<EmailReceiveRequest>) -> ApiResult<Json<EmailReceiveResponse>> {
+    if payload.from.trim().is_empty() || payload.to.trim().is_empty() {
+        return Err(ApiError::Validation("from, to 는 비어 있을 수 없음".into()));
+    }
+    let id = process_incoming_email(&payload.from, &payload.to, &payload.subject, &payload.body)
+        .await
+        .map_err(|e| ApiError::Internal(format!("이메일 처리 실패: {}", e)))?;
+    let job_id = if wants_classify(payload.classify) { Some(jobs::enqueue(&id, payload.callback_url, payload.session_id).await?.id) } else { None };
+    Ok(Json(EmailReceiveResponse { success: true, email_id: Some(id), job_id, message: "이메일 수신 성공".into() }))
+}
+
+/// AI 분류 (세션이 있으면 세션 설정 사용, 오류는 502, 시간 초과는 504)
+pub async fn classify_timed(email: &Email, session_id: Option<&str>) -> ApiResult<(String, f32)> {
+    let provider = session::resolve(session_id)?;
+    let timeout = provider.config.timeout;
+    match tokio::time::timeout(timeout, classify_with_provider(&provider, email)).await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err(ApiError::Upstream(format!("AI 분류 실패: {}", e))),
+        Err(_) => Err(ApiError::UpstreamTimeout(format!("AI 분류 응답 없음 ({}초)", timeout.as_secs()))),
+    }
+}
+
+// 분류 작업 등록 (202 + Location, 결과는 /api/jobs/{id} 또는 콜백)
+async fn classify_email(ApiJson(payload): ApiJson<ClassifyEmailRequest>) -> ApiResult<impl IntoResponse> {
+    let job = jobs::enqueue(&payload.email_id, payload.callback_url, payload.session_id).await?;
+    Ok((StatusCode::ACCEPTED, [(header::LOCATION, format!("/api/jobs/{}", job.id))], Json(job)))
+}
+
+// 분류 작업 조회
+async fn get_job(ApiPath(id): ApiPath<String>) -> ApiResult<Json<Job>> {
+    jobs::get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("작업을 찾을 수 없음: {}", id)))
+}
+
+// AI 연결 (키/모델 확인 후 세션 발급)
+async fn connect_ai(ApiJson(payload): ApiJson<AiConnectRequest>) -> ApiResult<Json<AiConnectResponse>> {
+    let info = session::connect(&payload.api_key, &payload.model, payload.base_url.as_deref(), payload.timeout_secs).await?;
+    Ok(Json(AiConnectResponse {
+        success: true,
+        session_id: Some(info.session_id),
+        expires_at: Some(info.expires_at),
+        message: "AI 연결 성공".into(),
+    }))
+}
+
+// AI 세션 조회 (키 제외)
+async fn get_ai_session(ApiPath(id): ApiPath<String>) -> ApiResult<Json<SessionInfo>> {
+    session::get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("AI 세션을 찾을 수 없음: {}", id)))
+}
+
+// AI 세션 종료
+async fn disconnect_ai(ApiPath(id): ApiPath<String>) -> ApiResult<StatusCode> {
+    if session::remove(&id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("AI 세션을 찾을 수 없음: {}", id)))
     }
 }
 
-// 분류
-async fn classify_email(Json(payload): Json<ClassifyEmailRequest>) -> Json<ClassifyEmailResponse> {
-    match get_email(&payload.email_id) {
-        Ok(email) => match classify_with_ai(&email).await {
-            Ok((cat, conf)) => Json(ClassifyEmailResponse { success: true, category: Some(cat), confidence: Some(conf), message: "분류 성공".into() }),
-            Err(e) => Json(ClassifyEmailResponse { success: false, category: None, confidence: None, message: format!("AI 분류 실패: {}", e) }),
-        },
-        Err(e) => Json(ClassifyEmailResponse { success: false, category: None, confidence: None, message: format!("이메일 조회 실패: {}", e) }),
+// 계정별 모니터링 상태
+async fn list_accounts() -> Json<Vec<AccountStatus>> {
+    Json(snapshot())
+}
+
+// 정의되지 않은 경로
+async fn not_found() -> ApiError {
+    ApiError::NotFound("요청한 경로가 없음".into())
+}
+
+/// 원문이 보관된 메일인지 확인 (메일 없으면 404, 원문 없으면 409)
+fn require_raw(id: &str) -> ApiResult<()> {
+    match find_email(id)?.raw_sha256 {
+        Some(_) => Ok(()),
+        None => Err(ApiError::Conflict(format!("원문이 보관되지 않은 메일: {}", id))),
     }
 }
 
-// AI 연결 (세션 매핑 생략하고 바로 성공 리턴)
-async fn connect_ai(Json(_): Json<AiConnectRequest>) -> Json<AiConnectResponse> {
-    // 별도 세션 관리 생략
-    Json(AiConnectResponse { success: true, session_id: None, message: "AI 연결 성공".into() })
-}
\ No newline at end of file
+// 보관된 원문 내보내기 (.eml)
+async fn export_raw(ApiPath(id): ApiPath<String>) -> ApiResult<impl IntoResponse> {
+    require_raw(&id)?;
+    let raw = raw_message(&id).map_err(|e| ApiError::Internal(format!("원문 조회 실패: {}", e)))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "message/rfc822".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.eml\"", id)),
+        ],
+        raw,
+    ))
+}
+
+// 보관된 원문을 현재 파서로 다시 파싱
+async fn reparse(ApiPath(id): ApiPath<String>) -> ApiResult<Json<Email>> {
+    require_raw(&id)?;
+    reparse_email(&id)
+        .map(Json)
+        .map_err(|e| ApiError::Internal(format!("재파싱 실패: {}", e)))
+}
+
+// 전문 검색 (from:, category:, before:, after:, "구문")
+async fn search_emails(ApiQuery(params): ApiQuery<SearchParams>) -> ApiResult<Json<SearchResponse>> {
+    let query = SearchQuery::parse(&params.q).map_err(ApiError::BadRequest)?;
+    let page = params.page.max(1);
+    let per_page = params.per_page.clamp(1, 100);
+    let result = store()
+        .search(&query, per_page, (page - 1) * per_page)
+        .map_err(|e| ApiError::Internal(format!("검색 실패: {}", e)))?;
+    Ok(Json(SearchResponse { query: params.q, total: result.total, page, per_page, hits: result.hits }))
+}
+
+fn encode_cursor(email: &EmailSummary) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}|{}", email.received_at.to_rfc3339(), email.id))
+}
+
+fn decode_cursor(raw: &str) -> Option<Cursor> {
+    let bytes = URL_SAFE_NO_PAD.decode(raw).ok()?;
+    let text = String::from_utf8(bytes).ok()?;
+    let (time, id) = text.split_once('|')?;
+    Some(Cursor { received_at: DateTime::parse_from_rfc3339(time).ok()?.with_timezone(&Utc), id: id.to_string() })
+}
+
+// 목록 (필터 + 커서 페이지네이션)
+async fn list_emails(ApiQuery(params): ApiQuery<ListParams>) -> ApiResult<Json<EmailListResponse>> {
+    let order = match params.order.as_deref() {
+        None | Some("newest") => SortOrder::NewestFirst,
+        Some("oldest") => SortOrder::OldestFirst,
+        Some(other) => return Err(ApiError::BadRequest(format!("order 는 newest 또는 oldest: {}", other))),
+    };
+    let filter = ListFilter {
+        category: params.category,
+        from: params.from,
+        since: params.since.as_deref().map(parse_date).transpose().map_err(ApiError::BadRequest)?,
+        until: params.until.as_deref().map(parse_date).transpose().map_err(ApiError::BadRequest)?,
+        processed: params.processed,
+        order,
+    };
+    let cursor = match params.cursor.as_deref() {
+        Some(raw) => Some(decode_cursor(raw).ok_or_else(|| ApiError::BadRequest("잘못된 cursor".into()))?),
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+
+    // 한 개 더 읽어서 다음 페이지 존재 여부 판단
+    let mut emails = store()
+        .list(&filter, cursor.as_ref(), limit + 1)
+        .map_err(|e| ApiError::Internal(format!("목록 조회 실패: {}", e)))?;
+    let has_more = emails.len() > limit;
+    emails.truncate(limit);
+    let items: Vec<EmailSummary> = emails.into_iter().map(EmailSummary::from).collect();
+    let next_cursor = if has_more { items.last().map(encode_cursor) } else { None };
+    Ok(Json(EmailListResponse { items, next_cursor }))
+}
+
+/// 저장소에서 메일 조회 (없으면 404)
+pub fn find_email(id: &str) -> ApiResult<Email> {
+    store()
+        .get(id)
+        .map_err(|e| ApiError::Internal(format!("이메일 조회 실패: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("이메일을 찾을 수 없음: {}", id)))
+}
+
+// 단건 조회
+async fn get_email_by_id(ApiPath(id): ApiPath<String>) -> ApiResult<Json<Email>> {
+    find_email(&id).map(Json)
+}
+
+// 삭제
+async fn delete_email(ApiPath(id): ApiPath<String>) -> ApiResult<StatusCode> {
+    match store().delete(&id) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(ApiError::NotFound(format!("이메일을 찾을 수 없음: {}", id))),
+        Err(e) => Err(ApiError::Internal(format!("삭제 실패: {}", e))),
+    }
+}
+
+// 수동 분류 지정
+async fn patch_email(ApiPath(id): ApiPath<String>, ApiJson(patch): ApiJson<EmailPatchRequest>) -> ApiResult<Json<Email>> {
+    find_email(&id)?;
+    let email = update_email(&id, |email| {
+        if let Some(category) = patch.category {
+            email.category = category.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+        }
+    })
+    .map_err(|e| ApiError::Internal(format!("수정 실패: {}", e)))?;
+    Ok(Json(email))
+}
@@ -0,0 +1,399 @@
+// master/src/auth.rs
+//
+// API 인증 미들웨어
+//   - API 키: X-API-Key 헤더 또는 Authorization: Bearer pk_… (키 파일에는 SHA-256 해시만 저장)
+//   - JWT: Authorization: Bearer <jwt> (HS256 또는 RS256)
+//   - 스코프: read (조회), classify (수신/분류/AI), admin (삭제/수정/재파싱 등, 모든 권한 포함)
+//   - 키(또는 JWT sub)별 분당 요청 수 제한 → 초과 시 429 + Retry-After
+//
+// 환경 변수
+//   API_AUTH               required (기본) | disabled (개발용, 경고 로그)
+//   API_KEYS_FILE          키 파일 (기본 api_keys.json, apikey CLI 로 관리, 바뀌면 자동 다시 읽음)
+//   API_RATE_LIMIT_PER_MIN 키에 한도가 없을 때 기본값 (기본 120, 0 이면 제한 없음)
+//   JWT_HS256_SECRET       HS256 공유 비밀
+//   JWT_RS256_PUBLIC_KEY   RS256 공개키 PEM 파일 경로
+//   JWT_ISSUER / JWT_AUDIENCE  지정하면 iss / aud 검사
+//
+// /api/inbound/* 는 서비스별 서명/토큰으로 따로 검증하므로 제외합니다.
+// EventSource 는 헤더를 못 넣으므로 /api/events 는 ?api_key= 도 받습니다.
+
+use crate::config::env_or;
+use crate::error::{ApiError, ApiResult};
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, warn};
+
+/// API 키 접두어
+pub const KEY_PREFIX: &str = "pk_";
+
+lazy_static::lazy_static! {
+    static ref KEYS: Mutex<KeyCache> = Mutex::new(KeyCache::default());
+    static ref BUCKETS: Mutex<Buckets> = Mutex::new(Buckets { map: HashMap::new(), swept: Instant::now() });
+    static ref JWT: Option<JwtConfig> = JwtConfig::from_env().unwrap_or_else(|e| {
+        warn!("[Auth] JWT 설정 오류 — JWT 인증 비활성: {}", e);
+        None
+    });
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    Classify,
+    Admin,
+}
+
+impl Scope {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read" => Some(Scope::Read),
+            "classify" => Some(Scope::Classify),
+            "admin" => Some(Scope::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Classify => "classify",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+/// 키 파일의 한 항목 (원래 키는 저장하지 않음)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub name: String,
+    /// 키 전체의 SHA-256 (16진수)
+    pub hash: String,
+    pub scopes: Vec<Scope>,
+    /// 분당 요청 수 (없으면 API_RATE_LIMIT_PER_MIN)
+    #[serde(default)]
+    pub rate_limit_per_min: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+/// 인증된 호출자 (요청 extensions 에 들어감)
+#[derive(Clone, Debug)]
+pub struct Principal {
+    /// "key:<id>" 또는 "jwt:<sub>"
+    pub id: String,
+    pub scopes: Vec<Scope>,
+    pub rate_limit_per_min: Option<u32>,
+}
+
+impl Principal {
+    pub fn has(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+// ───────────────────────── 키 파일 ─────────────────────────
+
+pub fn keys_file() -> PathBuf {
+    PathBuf::from(env::var("API_KEYS_FILE").unwrap_or_else(|_| "api_keys.json".to_string()))
+}
+
+pub fn load_keys() -> Result<Vec<ApiKeyRecord>> {
+    let path = keys_file();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(&path).with_context(|| format!("{} 읽기 실패", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("{} 파싱 실패", path.display()))
+}
+
+pub fn save_keys(keys: &[ApiKeyRecord]) -> Result<()> {
+    let path = keys_file();
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(keys)?).with_context(|| format!("{} 쓰기 실패", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("{} 저장 실패", path.display()))?;
+    Ok(())
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// 새 키 발급: (기록, 원래 키 — 이때 한 번만 보여 줌)
+pub fn generate_key(name: &str, scopes: Vec<Scope>, rate_limit_per_min: Option<u32>) -> (ApiKeyRecord, String) {
+    let id = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+    let secret = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let key = format!("{}{}_{}", KEY_PREFIX, id, secret);
+    let record = ApiKeyRecord {
+        id,
+        name: name.to_string(),
+        hash: hash_key(&key),
+        scopes,
+        rate_limit_per_min,
+        created_at: Utc::now(),
+        revoked: false,
+    };
+    (record, key)
+}
+
+/// 파일이 바뀌었을 때만 다시 읽는 캐시
+#[derive(Default)]
+struct KeyCache {
+    modified: Option<SystemTime>,
+    keys: HashMap<String, ApiKeyRecord>,
+}
+
+fn find_key(id: &str) -> ApiResult<Option<ApiKeyRecord>> {
+    let mut cache = KEYS.lock().map_err(|_| ApiError::Internal("키 캐시 잠금 실패".into()))?;
+    let modified = fs::metadata(keys_file()).and_then(|m| m.modified()).ok();
+    if modified != cache.modified {
+        let keys = load_keys().map_err(|e| ApiError::Internal(format!("API 키 파일 오류: {:#}", e)))?;
+        cache.keys = keys.into_iter().map(|k| (k.id.clone(), k)).collect();
+        cache.modified = modified;
+        debug!("[Auth] API 키 {}개 읽음", cache.keys.len());
+    }
+    Ok(cache.keys.get(id).cloned())
+}
+
+/// 비밀 값 비교 (길이 외에는 내용에 따라 걸리는 시간이 달라지지 않음)
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn verify_api_key(key: &str) -> ApiResult<Principal> {
+    let invalid = || ApiError::Unauthorized("유효하지 않은 API 키".into());
+    let id = key
+        .strip_prefix(KEY_PREFIX)
+        .and_then(|rest| rest.split_once('_'))
+        .map(|(id, _)| id)
+        .ok_or_else(invalid)?;
+    let record = find_key(id)?.ok_or_else(invalid)?;
+    if record.revoked || !constant_time_eq(hash_key(key).as_bytes(), record.hash.as_bytes()) {
+        return Err(invalid());
+    }
+    Ok(Principal { id: format!("key:{}", record.id), scopes: record.scopes, rate_limit_per_min: record.rate_limit_per_min })
+}
+
+// ───────────────────────── JWT ─────────────────────────
+
+struct JwtConfig {
+    keys: Vec<(Algorithm, DecodingKey)>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    /// 공백 구분 (OAuth 관례)
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+impl JwtConfig {
+    fn from_env() -> Result<Option<Self>> {
+        let mut keys = Vec::new();
+        if let Ok(secret) = env::var("JWT_HS256_SECRET") {
+            keys.push((Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes())));
+        }
+        if let Ok(path) = env::var("JWT_RS256_PUBLIC_KEY") {
+            let pem = fs::read(&path).with_context(|| format!("{} 읽기 실패", path))?;
+            keys.push((Algorithm::RS256, DecodingKey::from_rsa_pem(&pem).map_err(|e| anyhow!("RS256 공개키 오류: {}", e))?));
+        }
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(JwtConfig { keys, issuer: env::var("JWT_ISSUER").ok(), audience: env::var("JWT_AUDIENCE").ok() }))
+    }
+}
+
+fn verify_jwt(token: &str) -> ApiResult<Principal> {
+    let config = JWT
+        .as_ref()
+        .ok_or_else(|| ApiError::Unauthorized("JWT 인증이 설정되지 않음".into()))?;
+    let alg = jsonwebtoken::decode_header(token)
+        .map_err(|e| ApiError::Unauthorized(format!("잘못된 토큰: {}", e)))?
+        .alg;
+    // 헤더의 alg 와 설정된 키 종류가 같을 때만 (알고리즘 바꿔치기 방지)
+    let (_, key) = config
+        .keys
+        .iter()
+        .find(|(a, _)| *a == alg)
+        .ok_or_else(|| ApiError::Unauthorized(format!("허용되지 않은 알고리즘: {:?}", alg)))?;
+    let mut validation = Validation::new(alg);
+    validation.set_required_spec_claims(&["exp", "sub"]);
+    if let Some(iss) = &config.issuer {
+        validation.set_issuer(&[iss]);
+    }
+    match &config.audience {
+        Some(aud) => validation.set_audience(&[aud]),
+        None => validation.validate_aud = false,
+    }
+    let claims = decode::<Claims>(token, key, &validation)
+        .map_err(|e| ApiError::Unauthorized(format!("토큰 검증 실패: {}", e)))?
+        .claims;
+    let scopes = claims
+        .scope
+        .iter()
+        .flat_map(|s| s.split_whitespace())
+        .chain(claims.scopes.iter().map(String::as_str))
+        .filter_map(Scope::parse)
+        .collect();
+    Ok(Principal { id: format!("jwt:{}", claims.sub), scopes, rate_limit_per_min: None })
+}
+
+// ───────────────────────── 요청 수 제한 ─────────────────────────
+
+/// 가득 찬 채로 이만큼 지난 버킷은 정리 (다시 만들어도 같은 상태)
+const BUCKET_IDLE: Duration = Duration::from_secs(60);
+
+/// 토큰 버킷 (분당 limit 개, 최대 limit 개까지 몰아서 가능)
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// 이 시각이면 다시 가득 참
+    full_at: Instant,
+}
+
+struct Buckets {
+    map: HashMap<String, Bucket>,
+    /// 마지막 정리 시각
+    swept: Instant,
+}
+
+/// 허용이면 Ok, 아니면 다시 시도할 때까지 초
+fn take_token(principal: &Principal) -> Result<(), u64> {
+    let limit = principal
+        .rate_limit_per_min
+        .unwrap_or_else(|| env_or("API_RATE_LIMIT_PER_MIN", 120));
+    if limit == 0 {
+        return Ok(());
+    }
+    // 잠금이 오염돼도 제한 없이 통과시키지 않도록 그대로 이어 씀
+    let mut buckets = BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+    let per_sec = limit as f64 / 60.0;
+    let now = Instant::now();
+    if now.duration_since(buckets.swept) >= BUCKET_IDLE {
+        buckets.map.retain(|_, b| now.saturating_duration_since(b.full_at) < BUCKET_IDLE);
+        buckets.swept = now;
+    }
+    let bucket = buckets
+        .map
+        .entry(principal.id.clone())
+        .or_insert(Bucket { tokens: limit as f64, updated: now, full_at: now });
+    bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec).min(limit as f64);
+    bucket.updated = now;
+    let result = if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        Ok(())
+    } else {
+        Err(((1.0 - bucket.tokens) / per_sec).ceil() as u64)
+    };
+    bucket.full_at = now + Duration::from_secs_f64((limit as f64 - bucket.tokens) / per_sec);
+    result
+}
+
+// ───────────────────────── 미들웨어 ─────────────────────────
+
+/// 경로/메서드별 필요한 스코프 (None = 인증 없음)
+fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    if path.starts_with("/api/inbound/") {
+        return None;
+    }
+    if method == Method::OPTIONS {
+        return None;
+    }
+    if method == Method::GET || method == Method::HEAD {
+        return Some(Scope::Read);
+    }
+    // PATCH 는 분류 결과를 사람이 덮어쓰는 수정이므로 삭제/재파싱과 같이 admin
+    if method == Method::DELETE || method == Method::PATCH || path.ends_with("/reparse") {
+        return Some(Scope::Admin);
+    }
+    Some(Scope::Classify)
+}
+
+fn credential(headers: &HeaderMap, path: &str, query: Option<&str>) -> Option<String> {
+    if let Some(key) = headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+    if let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer ").or_else(|| v.strip_prefix("bearer ")))
+    {
+        return Some(token.trim().to_string());
+    }
+    if path.starts_with("/api/events") {
+        return query?
+            .split('&')
+            .filter_map(|kv| kv.split_once('='))
+            .find(|(k, _)| *k == "api_key")
+            .map(|(_, v)| v.to_string());
+    }
+    None
+}
+
+fn authenticate(req: &Request) -> ApiResult<Principal> {
+    let cred = credential(req.headers(), req.uri().path(), req.uri().query())
+        .ok_or_else(|| ApiError::Unauthorized("API 키 또는 Bearer 토큰이 필요함".into()))?;
+    if cred.starts_with(KEY_PREFIX) {
+        verify_api_key(&cred)
+    } else {
+        verify_jwt(&cred)
+    }
+}
+
+pub fn auth_disabled() -> bool {
+    env::var("API_AUTH").is_ok_and(|v| v.eq_ignore_ascii_case("disabled"))
+}
+
+/// 인증 + 스코프 확인 + 요청 수 제한
+pub async fn require_auth(mut req: Request, next: Next) -> Response {
+    let Some(scope) = required_scope(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+    if auth_disabled() {
+        return next.run(req).await;
+    }
+    let principal = match authenticate(&req) {
+        Ok(p) => p,
+        Err(e) => {
+            let mut res = e.into_response();
+            res.headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return res;
+        }
+    };
+    if !principal.has(scope) {
+        return ApiError::Forbidden(format!("{} 스코프가 필요함", scope.as_str())).into_response();
+    }
+    if let Err(retry_after) = take_token(&principal) {
+        return ApiError::RateLimited {
+            detail: format!("요청 수 제한 초과 ({})", principal.id),
+            retry_after,
+        }
+        .into_response();
+    }
+    debug!("[Auth] {} → {} {}", principal.id, req.method(), req.uri().path());
+    req.extensions_mut().insert(principal);
+    next.run(req).await
+}
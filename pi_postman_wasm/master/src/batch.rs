@@ -0,0 +1,537 @@
+// master/src/batch.rs
+//
+// 일괄 수신/분류
+//   POST /api/email/receive/batch   — JSON 배열 또는 NDJSON (Content-Type: application/x-ndjson)
+//   POST /api/email/classify/batch  — 같은 형식, ?mode=direct|provider|auto
+//   GET  /api/batches/{id}          — provider 모드 진행 상황
+//
+// direct 는 동시 처리 수를 제한해 바로 분류하고 항목별 결과를 돌려줍니다.
+// provider 는 OpenAI Batch API (JSONL 업로드 → 최대 24시간 내 처리) 를 써서 대량 백필 비용을 줄입니다.
+// ?session_id= 를 주면 그 AI 세션의 키/모델/주소를 씁니다.
+//
+// 환경 변수
+//   BATCH_MAX_ITEMS          요청당 최대 항목 수 (기본 1000)
+//   BATCH_CONCURRENCY        direct 모드 동시 분류 수 (기본 8)
+//   OPENAI_BATCH_API         1/true 면 provider 모드 사용 가능
+//   OPENAI_BATCH_MIN_ITEMS   auto 모드에서 provider 로 넘길 최소 항목 수 (기본 100)
+//   OPENAI_BATCH_POLL_SECS   provider 상태 확인 주기 (기본 60)
+
+use crate::ai::prompt_body;
+use crate::api::{classify_timed, wants_classify, ClassifyEmailRequest, EmailReceiveRequest};
+use crate::config::{env_flag, env_or};
+use crate::error::{ApiError, ApiPath, ApiQuery, ApiResult};
+use crate::jobs;
+use crate::session::{self, AiProvider};
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use common::classifier::{parse_classification, user_prompt, SYSTEM_PROMPT};
+use common::email::{process_incoming_email, set_category};
+use common::events::{publish_id, EventKind};
+use common::store::store;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{info, warn};
+
+/// 일괄 요청 본문 최대 크기
+pub const BATCH_MAX_BYTES: usize = 50 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    static ref BATCHES: Mutex<HashMap<String, ProviderBatch>> = Mutex::new(HashMap::new());
+}
+
+/// 항목별 실패 사유
+#[derive(Clone, Debug, Serialize)]
+pub struct ItemError {
+    pub code: &'static str,
+    pub detail: String,
+}
+
+impl From<ApiError> for ItemError {
+    fn from(e: ApiError) -> Self {
+        ItemError { code: e.code(), detail: e.detail().to_string() }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReceiveItemResult {
+    pub index: usize,
+    pub email_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    pub error: Option<ItemError>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ClassifyItemResult {
+    pub index: usize,
+    pub email_id: Option<String>,
+    pub category: Option<String>,
+    pub confidence: Option<f32>,
+    pub error: Option<ItemError>,
+}
+
+#[derive(Serialize)]
+pub struct BatchResponse<T> {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<T>,
+}
+
+#[derive(Deserialize)]
+pub struct ClassifyBatchParams {
+    /// direct | provider | auto (기본)
+    pub mode: Option<String>,
+    /// 항목에 session_id 가 없을 때 쓸 AI 세션 (provider 모드는 이 세션만 사용)
+    pub session_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchState {
+    Submitting,
+    InProgress,
+    Completed,
+    Failed,
+}
+
+/// provider 모드 일괄 분류 진행 상황
+#[derive(Clone, Debug, Serialize)]
+pub struct ProviderBatch {
+    pub id: String,
+    pub provider_batch_id: Option<String>,
+    /// 제공자 쪽 상태 문자열 (validating, in_progress, finalizing …)
+    pub provider_status: Option<String>,
+    pub state: BatchState,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub error: Option<String>,
+    pub items: Vec<ClassifyItemResult>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// JSON 배열 또는 NDJSON 본문을 항목 단위로 파싱 (잘못된 항목은 항목별 오류)
+fn parse_items<T: DeserializeOwned>(headers: &HeaderMap, body: &[u8]) -> ApiResult<Vec<Result<T, ItemError>>> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let values: Vec<Result<Value, String>> = if content_type.contains("ndjson") || content_type.contains("jsonl") {
+        let text = std::str::from_utf8(body).map_err(|_| ApiError::BadRequest("NDJSON 은 UTF-8 이어야 함".into()))?;
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| serde_json::from_str(l).map_err(|e| format!("JSON 파싱 실패: {}", e)))
+            .collect()
+    } else {
+        let values: Vec<Value> = serde_json::from_slice(body)
+            .map_err(|e| ApiError::BadRequest(format!("JSON 배열이 아님: {}", e)))?;
+        values.into_iter().map(Ok).collect()
+    };
+
+    let max = env_or("BATCH_MAX_ITEMS", 1000usize);
+    if values.is_empty() {
+        return Err(ApiError::Validation("항목이 없음".into()));
+    }
+    if values.len() > max {
+        return Err(ApiError::Validation(format!("항목이 너무 많음: {} (최대 {})", values.len(), max)));
+    }
+    Ok(values
+        .into_iter()
+        .map(|v| {
+            v.and_then(|v| serde_json::from_value(v).map_err(|e| e.to_string()))
+                .map_err(|detail| ItemError { code: "validation_failed", detail })
+        })
+        .collect())
+}
+
+fn summarize<T>(items: Vec<T>, failed: impl Fn(&T) -> bool) -> BatchResponse<T> {
+    let failed = items.iter().filter(|i| failed(i)).count();
+    BatchResponse { total: items.len(), succeeded: items.len() - failed, failed, items }
+}
+
+// ───────────────────────── 일괄 수신 ─────────────────────────
+
+async fn receive_one(item: EmailReceiveRequest) -> ApiResult<(String, Option<String>)> {
+    if item.from.trim().is_empty() || item.to.trim().is_empty() {
+        return Err(ApiError::Validation("from, to 는 비어 있을 수 없음".into()));
+    }
+    let id = process_incoming_email(&item.from, &item.to, &item.subject, &item.body)
+        .await
+        .map_err(|e| ApiError::Internal(format!("이메일 처리 실패: {}", e)))?;
+    let job_id = if wants_classify(item.classify) { Some(jobs::enqueue(&id, item.callback_url, item.session_id).await?.id) } else { None };
+    Ok((id, job_id))
+}
+
+/// 일괄 수신 (항목별 classify 또는 AUTO_CLASSIFY 면 분류 작업도 등록)
+pub async fn receive_batch(headers: HeaderMap, body: Bytes) -> ApiResult<Json<BatchResponse<ReceiveItemResult>>> {
+    let items = parse_items::<EmailReceiveRequest>(&headers, &body)?;
+    let mut results = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let result = match item {
+            Ok(item) => receive_one(item).await.map_err(ItemError::from),
+            Err(e) => Err(e),
+        };
+        results.push(match result {
+            Ok((email_id, job_id)) => ReceiveItemResult { index, email_id: Some(email_id), job_id, error: None },
+            Err(e) => ReceiveItemResult { index, email_id: None, job_id: None, error: Some(e) },
+        });
+    }
+    info!("[Batch] 일괄 수신 {}건", results.len());
+    Ok(Json(summarize(results, |r| r.error.is_some())))
+}
+
+// ───────────────────────── 일괄 분류 ─────────────────────────
+
+fn failed_item(index: usize, email_id: Option<String>, error: ItemError) -> ClassifyItemResult {
+    ClassifyItemResult { index, email_id, category: None, confidence: None, error: Some(error) }
+}
+
+/// 일괄 분류 (direct 는 200 + 결과, provider 는 202 + 진행 상황)
+pub async fn classify_batch(
+    ApiQuery(params): ApiQuery<ClassifyBatchParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    let items = parse_items::<ClassifyEmailRequest>(&headers, &body)?;
+    let provider_enabled = env_flag("OPENAI_BATCH_API");
+    let use_provider = match params.mode.as_deref() {
+        None | Some("auto") => provider_enabled && items.len() >= env_or("OPENAI_BATCH_MIN_ITEMS", 100usize),
+        Some("direct") => false,
+        Some("provider") if provider_enabled => true,
+        Some("provider") => return Err(ApiError::Conflict("OPENAI_BATCH_API 가 꺼져 있음".into())),
+        Some(other) => return Err(ApiError::BadRequest(format!("mode 는 direct, provider, auto 중 하나: {}", other))),
+    };
+
+    if use_provider {
+        let provider = session::resolve(params.session_id.as_deref())?;
+        let batch = submit_provider_batch(items, provider)?;
+        let location = format!("/api/batches/{}", batch.id);
+        return Ok((StatusCode::ACCEPTED, [(header::LOCATION, location)], Json(batch)).into_response());
+    }
+    Ok(Json(classify_direct(items, params.session_id).await).into_response())
+}
+
+async fn classify_direct(
+    items: Vec<Result<ClassifyEmailRequest, ItemError>>,
+    default_session: Option<String>,
+) -> BatchResponse<ClassifyItemResult> {
+    let sem = Arc::new(Semaphore::new(env_or("BATCH_CONCURRENCY", 8usize).max(1)));
+    let mut set = JoinSet::new();
+    let mut results = Vec::with_capacity(items.len());
+    // 패닉 등으로 결과 없이 끝난 작업도 항목별 실패로 돌려주기 위해 남은 항목 추적
+    let mut pending: HashMap<usize, String> = HashMap::new();
+    for (index, item) in items.into_iter().enumerate() {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                results.push(failed_item(index, None, e));
+                continue;
+            }
+        };
+        pending.insert(index, item.email_id.clone());
+        let sem = sem.clone();
+        let session_id = item.session_id.or_else(|| default_session.clone());
+        set.spawn(async move {
+            let _permit = sem.acquire_owned().await;
+            let id = item.email_id;
+            let outcome = async {
+                let email = store()
+                    .get(&id)?
+                    .ok_or_else(|| ApiError::NotFound(format!("이메일을 찾을 수 없음: {}", id)))?;
+                let (cat, conf) = classify_timed(&email, session_id.as_deref()).await?;
+                set_category(&id, &cat)?;
+                Ok::<_, ApiError>((cat, conf))
+            }
+            .await;
+            match outcome {
+                Ok((cat, conf)) => ClassifyItemResult {
+                    index,
+                    email_id: Some(id),
+                    category: Some(cat),
+                    confidence: Some(conf),
+                    error: None,
+                },
+                Err(e) => {
+                    publish_id(EventKind::Failed, &id, Some(e.detail().to_string()));
+                    failed_item(index, Some(id), e.into())
+                }
+            }
+        });
+    }
+    let mut aborted = None;
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(result) => {
+                pending.remove(&result.index);
+                results.push(result);
+            }
+            Err(e) => {
+                warn!("[Batch] 분류 작업 중단: {}", e);
+                aborted = Some(e.to_string());
+            }
+        }
+    }
+    for (index, id) in pending {
+        let detail = format!("분류 작업 중단: {}", aborted.as_deref().unwrap_or("알 수 없는 오류"));
+        publish_id(EventKind::Failed, &id, Some(detail.clone()));
+        results.push(failed_item(index, Some(id), ApiError::Internal(detail).into()));
+    }
+    results.sort_by_key(|r| r.index);
+    info!("[Batch] 일괄 분류(direct) {}건", results.len());
+    summarize(results, |r| r.error.is_some())
+}
+
+// ───────────────────────── OpenAI Batch API ─────────────────────────
+
+/// provider 모드 진행 상황 조회
+pub async fn get_batch(ApiPath(id): ApiPath<String>) -> ApiResult<Json<ProviderBatch>> {
+    BATCHES
+        .lock()
+        .ok()
+        .and_then(|b| b.get(&id).cloned())
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("일괄 작업을 찾을 수 없음: {}", id)))
+}
+
+fn update_batch(id: &str, f: impl FnOnce(&mut ProviderBatch)) {
+    if let Ok(mut batches) = BATCHES.lock() {
+        if let Some(b) = batches.get_mut(id) {
+            f(b);
+        }
+    }
+}
+
+fn submit_provider_batch(
+    items: Vec<Result<ClassifyEmailRequest, ItemError>>,
+    provider: AiProvider,
+) -> ApiResult<ProviderBatch> {
+    let config = &provider.config;
+    // custom_id 로 email_id 를 쓰므로 중복은 첫 항목만 제출
+    let mut seen = HashSet::new();
+    let mut results = Vec::new();
+    let mut requests = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match item {
+            Err(e) => results.push(failed_item(index, None, e)),
+            Ok(item) if !seen.insert(item.email_id.clone()) => results.push(failed_item(
+                index,
+                Some(item.email_id),
+                ItemError { code: "conflict", detail: "같은 요청 안에서 중복된 email_id".into() },
+            )),
+            Ok(item) => match store().get(&item.email_id)? {
+                Some(email) => {
+                    requests.push(json!({
+                        "custom_id": email.id,
+                        "method": "POST",
+                        "url": "/v1/chat/completions",
+                        "body": {
+                            "model": &config.model,
+                            "messages": [
+                                { "role": "system", "content": SYSTEM_PROMPT },
+                                { "role": "user", "content": user_prompt(&email.subject, &prompt_body(&email)) },
+                            ],
+                        },
+                    }));
+                    results.push(ClassifyItemResult { index, email_id: Some(email.id), category: None, confidence: None, error: None });
+                }
+                None => {
+                    let detail = format!("이메일을 찾을 수 없음: {}", item.email_id);
+                    results.push(failed_item(index, Some(item.email_id), ItemError { code: "not_found", detail }));
+                }
+            },
+        }
+    }
+    if requests.is_empty() {
+        return Err(ApiError::Validation("제출할 수 있는 항목이 없음".into()));
+    }
+
+    let batch = ProviderBatch {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider_batch_id: None,
+        provider_status: None,
+        state: BatchState::Submitting,
+        total: results.len(),
+        succeeded: 0,
+        failed: results.iter().filter(|r| r.error.is_some()).count(),
+        error: None,
+        items: results,
+        created_at: Utc::now(),
+        finished_at: None,
+    };
+    {
+        let mut batches = BATCHES.lock().map_err(|_| ApiError::Internal("일괄 작업 목록 잠금 실패".into()))?;
+        jobs::prune_finished(&mut batches, |b| b.finished_at);
+        batches.insert(batch.id.clone(), batch.clone());
+    }
+
+    let id = batch.id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run_provider_batch(&id, requests, OpenAiBatchClient::new(&provider)).await {
+            warn!("[Batch] {} provider 일괄 분류 실패: {:#}", id, e);
+            update_batch(&id, |b| {
+                b.state = BatchState::Failed;
+                b.error = Some(format!("{:#}", e));
+                b.finished_at = Some(Utc::now());
+            });
+        }
+    });
+    Ok(batch)
+}
+
+struct OpenAiBatchClient {
+    http: reqwest::Client,
+    base: String,
+    key: String,
+}
+
+impl OpenAiBatchClient {
+    /// 세션 제공자의 클라이언트를 그대로 사용 (검사한 주소 고정)
+    fn new(provider: &AiProvider) -> Self {
+        let config = &provider.config;
+        OpenAiBatchClient { http: provider.http.clone(), base: config.base().to_string(), key: config.api_key.clone() }
+    }
+
+    async fn json(&self, req: reqwest::RequestBuilder) -> Result<Value> {
+        let resp = req.bearer_auth(&self.key).send().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("OpenAI 응답 {}: {}", status, body));
+        }
+        serde_json::from_str(&body).context("OpenAI 응답 JSON 파싱 실패")
+    }
+
+    async fn upload(&self, jsonl: Vec<u8>) -> Result<String> {
+        let part = reqwest::multipart::Part::bytes(jsonl)
+            .file_name("batch.jsonl")
+            .mime_str("application/jsonl")?;
+        let form = reqwest::multipart::Form::new().text("purpose", "batch").part("file", part);
+        let v = self.json(self.http.post(format!("{}/files", self.base)).multipart(form)).await?;
+        id_of(&v)
+    }
+
+    async fn create(&self, file_id: &str) -> Result<Value> {
+        let body = json!({
+            "input_file_id": file_id,
+            "endpoint": "/v1/chat/completions",
+            "completion_window": "24h",
+        });
+        self.json(self.http.post(format!("{}/batches", self.base)).json(&body)).await
+    }
+
+    async fn status(&self, batch_id: &str) -> Result<Value> {
+        self.json(self.http.get(format!("{}/batches/{}", self.base, batch_id))).await
+    }
+
+    async fn content(&self, file_id: &str) -> Result<String> {
+        let resp = self
+            .http
+            .get(format!("{}/files/{}/content", self.base, file_id))
+            .bearer_auth(&self.key)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(resp.text().await?)
+    }
+}
+
+fn id_of(v: &Value) -> Result<String> {
+    v["id"].as_str().map(str::to_string).ok_or_else(|| anyhow!("응답에 id 없음: {}", v))
+}
+
+async fn run_provider_batch(id: &str, requests: Vec<Value>, client: OpenAiBatchClient) -> Result<()> {
+    let mut jsonl = Vec::new();
+    for r in &requests {
+        serde_json::to_writer(&mut jsonl, r)?;
+        jsonl.push(b'\n');
+    }
+    let file_id = client.upload(jsonl).await.context("입력 파일 업로드 실패")?;
+    let created = client.create(&file_id).await.context("일괄 작업 생성 실패")?;
+    let provider_id = id_of(&created)?;
+    info!("[Batch] {} → OpenAI batch {} ({}건)", id, provider_id, requests.len());
+    update_batch(id, |b| {
+        b.provider_batch_id = Some(provider_id.clone());
+        b.provider_status = created["status"].as_str().map(str::to_string);
+        b.state = BatchState::InProgress;
+    });
+
+    let poll = Duration::from_secs(env_or("OPENAI_BATCH_POLL_SECS", 60u64).max(5));
+    let status = loop {
+        tokio::time::sleep(poll).await;
+        let status = client.status(&provider_id).await?;
+        let state = status["status"].as_str().unwrap_or("").to_string();
+        update_batch(id, |b| b.provider_status = Some(state.clone()));
+        match state.as_str() {
+            "completed" => break status,
+            "failed" | "expired" | "cancelled" => return Err(anyhow!("OpenAI batch 상태: {}", state)),
+            _ => {}
+        }
+    };
+
+    // 출력/오류 파일의 각 줄: { custom_id, response: { status_code, body }, error }
+    let mut outcomes: HashMap<String, Result<(String, f32), String>> = HashMap::new();
+    for key in ["output_file_id", "error_file_id"] {
+        let Some(file_id) = status[key].as_str() else { continue };
+        for line in client.content(file_id).await?.lines().filter(|l| !l.trim().is_empty()) {
+            let v: Value = serde_json::from_str(line)?;
+            let Some(custom_id) = v["custom_id"].as_str() else { continue };
+            let outcome = if v["response"]["status_code"].as_u64() == Some(200) {
+                let content = v["response"]["body"]["choices"][0]["message"]["content"].as_str().unwrap_or("");
+                parse_classification(content).map_err(|e| e.to_string())
+            } else {
+                Err(v["error"]["message"]
+                    .as_str()
+                    .or(v["response"]["body"]["error"]["message"].as_str())
+                    .unwrap_or("OpenAI 처리 실패")
+                    .to_string())
+            };
+            outcomes.insert(custom_id.to_string(), outcome);
+        }
+    }
+
+    let applied: HashMap<String, Result<(String, f32), String>> = outcomes
+        .into_iter()
+        .map(|(email_id, outcome)| {
+            let outcome = outcome.and_then(|(cat, conf)| {
+                set_category(&email_id, &cat).map_err(|e| e.to_string())?;
+                Ok((cat, conf))
+            });
+            if let Err(e) = &outcome {
+                publish_id(EventKind::Failed, &email_id, Some(e.clone()));
+            }
+            (email_id, outcome)
+        })
+        .collect();
+    update_batch(id, |b| {
+        for item in b.items.iter_mut().filter(|i| i.error.is_none()) {
+            let Some(email_id) = &item.email_id else { continue };
+            match applied.get(email_id) {
+                Some(Ok((cat, conf))) => {
+                    item.category = Some(cat.clone());
+                    item.confidence = Some(*conf);
+                }
+                Some(Err(e)) => item.error = Some(ItemError { code: "upstream_error", detail: e.clone() }),
+                None => item.error = Some(ItemError { code: "upstream_error", detail: "결과 없음".into() }),
+            }
+        }
+        b.failed = b.items.iter().filter(|i| i.error.is_some()).count();
+        b.succeeded = b.total - b.failed;
+        b.state = BatchState::Completed;
+        b.finished_at = Some(Utc::now());
+    });
+    info!("[Batch] {} 완료 ({}건 결과)", id, applied.len());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//master/src/bin/apikey.rs
+//
+// API 키 관리 CLI (키 파일: API_KEYS_FILE, 기본 api_keys.json)
+//
+//   apikey create <이름> [--scopes read,classify,admin] [--rate <분당 요청 수>]
+//   apikey list
+//   apikey revoke <id>
+//
+// 실행 중인 서버는 키 파일이 바뀌면 다시 읽으므로 재시작할 필요가 없습니다.
+
+use anyhow::{anyhow, bail, Result};
+use dotenv::dotenv;
+use master::auth::{generate_key, keys_file, load_keys, save_keys, Scope};
+
+const USAGE: &str = "사용법:
+  apikey create <이름> [--scopes read,classify,admin] [--rate <분당 요청 수>]
+  apikey list
+  apikey revoke <id>";
+
+fn main() {
+    dotenv().ok();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("오류: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<()> {
+    match args.first().map(String::as_str) {
+        Some("create") => create(&args[1..]),
+        Some("list") => list(),
+        Some("revoke") => revoke(args.get(1).ok_or_else(|| anyhow!("revoke 할 id 가 필요함\n{}", USAGE))?),
+        _ => bail!("{}", USAGE),
+    }
+}
+
+fn create(args: &[String]) -> Result<()> {
+    let mut name = None;
+    let mut scopes = vec![Scope::Read];
+    let mut rate = None;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--scopes" => {
+                let value = it.next().ok_or_else(|| anyhow!("--scopes 값이 필요함"))?;
+                scopes = value
+                    .split(',')
+                    .filter(|s| !s.trim().is_empty())
+                    .map(|s| Scope::parse(s).ok_or_else(|| anyhow!("알 수 없는 스코프: {}", s)))
+                    .collect::<Result<_>>()?;
+            }
+            "--rate" => {
+                let value = it.next().ok_or_else(|| anyhow!("--rate 값이 필요함"))?;
+                rate = Some(value.parse().map_err(|_| anyhow!("--rate 는 숫자여야 함: {}", value))?);
+            }
+            other if other.starts_with("--") => bail!("알 수 없는 옵션: {}\n{}", other, USAGE),
+            other => name = Some(other.to_string()),
+        }
+    }
+    let name = name.ok_or_else(|| anyhow!("키 이름이 필요함\n{}", USAGE))?;
+    if scopes.is_empty() {
+        bail!("스코프가 하나 이상 필요함");
+    }
+
+    let mut keys = load_keys()?;
+    let (record, key) = generate_key(&name, scopes, rate);
+    println!("키 생성: {} ({})", record.id, record.name);
+    println!("스코프: {}", record.scopes.iter().map(Scope::as_str).collect::<Vec<_>>().join(","));
+    keys.push(record);
+    save_keys(&keys)?;
+    println!("\n{}\n\n이 키는 다시 표시되지 않습니다. 안전한 곳에 보관하세요.", key);
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let keys = load_keys()?;
+    if keys.is_empty() {
+        println!("등록된 키 없음 ({})", keys_file().display());
+        return Ok(());
+    }
+    println!("{:<14} {:<20} {:<22} {:<8} {:<8} 생성", "ID", "이름", "스코프", "분당", "상태");
+    for k in keys {
+        let scopes = k.scopes.iter().map(Scope::as_str).collect::<Vec<_>>().join(",");
+        let rate = k.rate_limit_per_min.map(|r| r.to_string()).unwrap_or_else(|| "기본".into());
+        let state = if k.revoked { "폐기" } else { "사용" };
+        println!(
+            "{:<14} {:<20} {:<22} {:<8} {:<8} {}",
+            k.id,
+            k.name,
+            scopes,
+            rate,
+            state,
+            k.created_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
+fn revoke(id: &str) -> Result<()> {
+    let mut keys = load_keys()?;
+    let key = keys
+        .iter_mut()
+        .find(|k| k.id == id)
+        .ok_or_else(|| anyhow!("키를 찾을 수 없음: {}", id))?;
+    if key.revoked {
+        println!("이미 폐기된 키: {} ({})", key.id, key.name);
+        return Ok(());
+    }
+    key.revoked = true;
+    println!("키 폐기: {} ({})", key.id, key.name);
+    save_keys(&keys)?;
+    Ok(())
+}
@@ -1,7 +1,7 @@
 //master/src/bin/server.rs
 
 use chrono::Local;
-use common::gmail::{connect_to_gmail, fetch_unseen_emails, GmailConfig};
+use common::gmail::{AsyncSession, GmailConfig};
 use common::classifier::classify_via_openai;
 use common::discord::send_discord_alert;
 use dotenv::dotenv;
@@ -31,38 +31,44 @@ async fn main() {
     info!("[Notifier] shard {}/{} 시작 — 동시처리={}", worker_id, total, concurrency);
 
     loop {
-        match connect_to_gmail(&cfg).await {
+        match AsyncSession::connect(&cfg).await {
             Ok(mut session) => {
-                if let Ok(emails) = fetch_unseen_emails(&mut session) {
-                    for em in emails {
-                        // 샤딩: UID 해시 % TOTAL == WORKER_ID
-                        let h = fxhash::hash32(em.uid.as_bytes()) as u64 % total;
-                        if h != worker_id { continue; }
-
-                        let permit = sem.clone().acquire_owned().await.unwrap();
+                // 샤딩: UID 해시 % TOTAL == WORKER_ID
+                let mine = |uid: u32| fxhash::hash32(uid.to_string().as_bytes()) as u64 % total == worker_id;
+                let result = session
+                    .for_each_unseen("INBOX", None, mine, |em| {
+                        let sem = sem.clone();
                         let hook = webhook.clone();
-                        let subj = em.subject.clone();
-                        let sndr = em.from.clone();
-                        let body = em.body.clone();
+                        async move {
+                            let permit = sem.acquire_owned().await.unwrap();
+                            let subj = em.subject.clone();
+                            let sndr = em.from.clone();
+                            let body = em.body.clone();
+                            let link = em.gmail_link.clone();
 
-                        task::spawn(async move {
-                            let now = Local::now();
-                            info!("[{}] 처리 시작: {}", now.format("%Y-%m-%d %H:%M:%S"), subj);
-                            match classify_via_openai(&subj, &body).await {
-                                Ok((cat, _)) => {
-                                    if let Err(e) = send_discord_alert(&hook, &subj, &sndr, &cat).await {
-                                        error!("[Discord] 전송 실패: {}", e);
+                            task::spawn(async move {
+                                let now = Local::now();
+                                info!("[{}] 처리 시작: {}", now.format("%Y-%m-%d %H:%M:%S"), subj);
+                                match classify_via_openai(&subj, &body).await {
+                                    Ok((cat, _)) => {
+                                        if let Err(e) = send_discord_alert(&hook, &subj, &sndr, &cat, Some(&link)).await {
+                                            error!("[Discord] 전송 실패: {}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        error!("[AI] 분류 실패: {}", e);
                                     }
                                 }
-                                Err(e) => {
-                                    error!("[AI] 분류 실패: {}", e);
-                                }
-                            }
-                            drop(permit);
-                        });
-                    }
+                                drop(permit);
+                            });
+                            true
+                        }
+                    })
+                    .await;
+                if let Err(e) = result {
+                    error!("[Gmail] 조회 실패: {}", e);
                 }
-                let _ = session.logout();
+                session.logout().await;
             }
             Err(e) => error!("[Gmail] 연결 실패: {}", e),
         }
@@ -0,0 +1,15 @@
+// master/src/config.rs
+//
+// 환경 변수 설정 읽기 공용 함수
+
+use std::env;
+
+/// 환경 변수를 파싱해서 읽고, 없거나 형식이 틀리면 기본값
+pub fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+}
+
+/// 1 또는 true (대소문자 무시) 면 켜짐
+pub fn env_flag(key: &str) -> bool {
+    env::var(key).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
@@ -1,7 +1,17 @@
 // master/src/email.rs
-use anyhow::Result;
-use common::email::{process_incoming_email, get_email};
-use crate::ai::classify_with_ai;
+use anyhow::{anyhow, Result};
+use common::attachment::{attachments_for_prompt, DEFAULT_ATTACHMENT_TEXT_BUDGET};
+use common::classifier::classify_via_openai;
+use common::discord::send_discord_alert;
+use common::events::{publish_id, EventKind};
+use common::archive::load_raw;
+use common::email::{process_incoming_email, store_incoming, get_email, set_category, update_email, Email};
+use common::gmail::parse_message;
+use common::gmail::ParsedEmail;
+use crate::ai::{classify_with_ai, prompt_body};
+use crate::config::env_or;
+use std::env;
+use tracing::{error, info};
 
 /// process_incoming_email를 래핑합니다.
 pub async fn receive_email(
@@ -17,4 +27,80 @@ pub async fn receive_email(
 pub async fn classify_email(email_id: &str) -> Result<(String, f32)> {
     let email = get_email(email_id)?;
     classify_with_ai(&email).await
-}
\ No newline at end of file
+}
+
+/// 웹훅/SMTP 등으로 들어온 메일을 저장하고, 분류 + Discord 알림은 백그라운드로 진행
+///
+/// 반환값은 저장된 이메일 ID 입니다.
+pub async fn ingest_parsed(em: ParsedEmail, source: &str) -> Result<String> {
+    // notifier 와 같은 방식으로 헤더 요약 + 본문 + 첨부 텍스트를 분류에 사용
+    let email = em.to_email();
+    let mut body = prompt_body(&email);
+    let budget = env_or("ATTACHMENT_TEXT_BUDGET", DEFAULT_ATTACHMENT_TEXT_BUDGET);
+    let attachment_text = attachments_for_prompt(&em.attachments, budget);
+    if !attachment_text.is_empty() {
+        body = format!("{}\n\n{}", body, attachment_text);
+    }
+
+    let id = store_incoming(email).await?;
+    info!("[{}] 메일 수신: {} (id={}, 첨부 {}개)", source, em.subject, id, em.attachments.len());
+
+    let source = source.to_string();
+    let email_id = id.clone();
+    tokio::spawn(async move {
+        let cat = match classify_via_openai(&em.subject, &body).await {
+            Ok((cat, score)) => {
+                info!("[{}] 분류 완료: {} ({})", source, cat, score);
+                cat
+            }
+            Err(e) => {
+                error!("[{}] AI 분류 실패: {}", source, e);
+                publish_id(EventKind::Failed, &email_id, Some(format!("AI 분류 실패: {}", e)));
+                return;
+            }
+        };
+        if let Err(e) = set_category(&email_id, &cat) {
+            error!("[{}] 분류 결과 저장 실패: {}", source, e);
+        }
+        if let Ok(hook) = env::var("DISCORD_WEBHOOK_URL") {
+            match send_discord_alert(&hook, &em.subject, &em.from, &cat, None).await {
+                Ok(()) => publish_id(EventKind::Notified, &email_id, None),
+                Err(e) => {
+                    error!("[{}] Discord 전송 실패: {}", source, e);
+                    publish_id(EventKind::Failed, &email_id, Some(format!("Discord 전송 실패: {}", e)));
+                }
+            }
+        }
+    });
+    Ok(id)
+}
+
+/// 보관된 원문 (원문이 없는 메일은 오류)
+pub fn raw_message(email_id: &str) -> Result<Vec<u8>> {
+    let email = get_email(email_id)?;
+    let hash = email
+        .raw_sha256
+        .ok_or_else(|| anyhow!("원문이 보관되지 않은 메일: {}", email_id))?;
+    load_raw(&hash)
+}
+
+/// 보관된 원문을 현재 파서로 다시 파싱해 제목/보낸이/본문/헤더/첨부 텍스트 갱신
+pub fn reparse_email(email_id: &str) -> Result<Email> {
+    let raw = raw_message(email_id)?;
+    let em = parse_message(0, &raw);
+    let attachment_text = em.attachment_text();
+    update_email(email_id, |email| {
+        email.from = em.from;
+        email.to = em
+            .headers
+            .to
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        email.subject = em.subject;
+        email.attachment_text = attachment_text;
+        email.body = em.body;
+        email.headers = em.headers;
+    })
+}
@@ -0,0 +1,174 @@
+// master/src/events.rs
+//
+// 실시간 이벤트 피드 (대시보드/TUI 용)
+//   GET /api/events     — SSE (id = 이벤트 번호, event = received|classified|notified|failed)
+//   GET /api/events/ws  — WebSocket (텍스트 프레임에 이벤트 JSON)
+//
+// ?category=spam,finance  카테고리 필터 (대소문자 무시)
+// ?kinds=classified,failed 이벤트 종류 필터
+// Last-Event-ID 헤더 또는 ?last_event_id= 로 끊긴 지점부터 이어 받음 (링 버퍼에 남은 만큼)
+// 버퍼에서 이미 밀려난 구간이 있거나 서버가 재시작됐으면 먼저 gap 이벤트를 보냅니다.
+
+use crate::error::{ApiError, ApiQuery, ApiResult};
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use common::events::{self, EventFilter, EventKind, MailEvent};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tracing::debug;
+
+#[derive(Deserialize)]
+pub struct EventParams {
+    /// 쉼표로 구분
+    pub category: Option<String>,
+    /// 쉼표로 구분 (received, classified, notified, failed)
+    pub kinds: Option<String>,
+    pub last_event_id: Option<u64>,
+}
+
+/// 피드 항목
+enum FeedItem {
+    Event(MailEvent),
+    /// 요청한 지점 이후 일부 이벤트를 더 이상 줄 수 없음
+    Gap,
+}
+
+impl EventParams {
+    fn filter(&self) -> ApiResult<EventFilter> {
+        let split = |s: &Option<String>| -> Vec<String> {
+            s.as_deref()
+                .unwrap_or("")
+                .split(',')
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+        let kinds = split(&self.kinds)
+            .iter()
+            .map(|k| EventKind::parse(k).ok_or_else(|| ApiError::BadRequest(format!("알 수 없는 이벤트 종류: {}", k))))
+            .collect::<ApiResult<Vec<_>>>()?;
+        Ok(EventFilter { categories: split(&self.category), kinds })
+    }
+
+    /// 쿼리 값이 있으면 우선, 없으면 Last-Event-ID 헤더
+    fn resume_from(&self, headers: &HeaderMap) -> ApiResult<Option<u64>> {
+        if self.last_event_id.is_some() {
+            return Ok(self.last_event_id);
+        }
+        match headers.get("last-event-id").and_then(|v| v.to_str().ok()) {
+            Some(v) => v
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| ApiError::BadRequest(format!("잘못된 Last-Event-ID: {}", v))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// 구독해서 필터에 맞는 이벤트를 채널로 넘김 (받는 쪽이 끊으면 종료)
+fn spawn_feed(filter: EventFilter, after: Option<u64>) -> mpsc::Receiver<FeedItem> {
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        let sub = events::subscribe(after);
+        let mut rx = sub.rx;
+        let mut last = after.unwrap_or(0);
+        if sub.gap && tx.send(FeedItem::Gap).await.is_err() {
+            return;
+        }
+        let mut pending = sub.replay;
+        loop {
+            for event in pending.drain(..) {
+                if event.id <= last {
+                    continue;
+                }
+                last = event.id;
+                if filter.matches(&event) && tx.send(FeedItem::Event(event)).await.is_err() {
+                    return;
+                }
+            }
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(event) => pending.push(event),
+                    // 밀렸으면 링 버퍼에서 다시 채움
+                    Err(RecvError::Lagged(n)) => {
+                        debug!("[Events] 구독자 {}건 밀림 — 버퍼에서 재생", n);
+                        let (replay, gap) = events::replay_since(last);
+                        if gap && tx.send(FeedItem::Gap).await.is_err() {
+                            return;
+                        }
+                        pending = replay;
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                _ = tx.closed() => return,
+            }
+        }
+    });
+    rx
+}
+
+fn gap_json() -> String {
+    json!({ "type": "gap", "message": "일부 이벤트가 버퍼에서 밀려나 전달되지 않음" }).to_string()
+}
+
+/// SSE 이벤트 피드
+pub async fn sse(
+    ApiQuery(params): ApiQuery<EventParams>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<impl tokio_stream::Stream<Item = Result<Event, Infallible>>>> {
+    let filter = params.filter()?;
+    let after = params.resume_from(&headers)?;
+    let stream = ReceiverStream::new(spawn_feed(filter, after)).map(|item| {
+        Ok(match item {
+            FeedItem::Event(e) => Event::default()
+                .id(e.id.to_string())
+                .event(e.kind.as_str())
+                .data(serde_json::to_string(&e).unwrap_or_default()),
+            FeedItem::Gap => Event::default().event("gap").data(gap_json()),
+        })
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// WebSocket 이벤트 피드 (클라이언트 메시지는 무시, Close 나 끊김까지 유지)
+pub async fn ws(
+    ws: WebSocketUpgrade,
+    ApiQuery(params): ApiQuery<EventParams>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let filter = params.filter()?;
+    let after = params.resume_from(&headers)?;
+    Ok(ws.on_upgrade(move |socket| run_ws(socket, filter, after)))
+}
+
+async fn run_ws(mut socket: WebSocket, filter: EventFilter, after: Option<u64>) {
+    let mut feed = spawn_feed(filter, after);
+    loop {
+        tokio::select! {
+            item = feed.recv() => {
+                let text = match item {
+                    Some(FeedItem::Event(e)) => serde_json::to_string(&e).unwrap_or_default(),
+                    Some(FeedItem::Gap) => gap_json(),
+                    None => break,
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
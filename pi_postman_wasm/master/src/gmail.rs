@@ -1,2 +1,2 @@
 // master/src/gmail.rs
-pub use common::gmail::{GmailConfig, ParsedEmail, connect_to_gmail, fetch_unseen_emails};
+pub use common::gmail::{AsyncSession, GmailConfig, ParsedEmail, connect_to_gmail, fetch_unseen_uid, mark_seen, search_unseen};
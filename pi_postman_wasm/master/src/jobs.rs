@@ -0,0 +1,259 @@
+// master/src/jobs.rs
+//
+// 분류 작업 큐: 요청은 작업 ID 만 받고 바로 돌아가고, 워커가 동시 처리 수를 제한해 분류합니다.
+// 결과는 GET /api/jobs/{id} 로 조회하거나, callback_url 을 주면 완료 시 POST 로 받습니다.
+//
+// 환경 변수
+//   CLASSIFY_CONCURRENCY  동시 분류 수 (기본 4)
+//   CLASSIFY_QUEUE_SIZE   대기열 최대 길이 (기본 1000, 넘치면 503)
+//   JOB_RETENTION_SECS    끝난 작업 보관 시간 (기본 3600)
+//   JOB_CALLBACK_SECRET   있으면 콜백 본문을 HMAC-SHA256 으로 서명 (X-Postman-Signature)
+//   JOB_CALLBACK_HOSTS    콜백을 허용할 호스트 목록 (쉼표 구분, 없으면 공인 주소만 허용)
+
+use crate::api::classify_timed;
+use crate::config::env_or;
+use crate::error::{ApiError, ApiResult};
+use crate::outbound;
+use crate::session;
+use chrono::{DateTime, Utc};
+use common::email::set_category;
+use common::events::{publish_id, EventKind};
+use common::store::store;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{info, warn};
+
+/// 콜백 시도 횟수
+const CALLBACK_ATTEMPTS: u32 = 3;
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+/// 콜백 허용 호스트 목록 환경 변수
+const CALLBACK_HOSTS_ENV: &str = "JOB_CALLBACK_HOSTS";
+
+lazy_static::lazy_static! {
+    static ref JOBS: Mutex<HashMap<String, Job>> = Mutex::new(HashMap::new());
+    // 첫 enqueue 때 워커 시작 (tokio 런타임 안에서 호출돼야 함)
+    static ref QUEUE: mpsc::Sender<String> = start_workers();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// 실패 사유 (problem+json 의 code/detail 과 같은 값)
+#[derive(Clone, Debug, Serialize)]
+pub struct JobError {
+    pub code: &'static str,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub email_id: String,
+    pub state: JobState,
+    pub category: Option<String>,
+    pub confidence: Option<f32>,
+    pub error: Option<JobError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// 분류에 사용할 AI 세션
+    #[serde(skip)]
+    pub session_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, JobState::Succeeded | JobState::Failed)
+    }
+}
+
+fn start_workers() -> mpsc::Sender<String> {
+    let concurrency = env_or("CLASSIFY_CONCURRENCY", 4usize).max(1);
+    let (tx, mut rx) = mpsc::channel::<String>(env_or("CLASSIFY_QUEUE_SIZE", 1000usize).max(1));
+    let sem = Arc::new(Semaphore::new(concurrency));
+    info!("[Jobs] 분류 워커 시작 (동시처리={})", concurrency);
+    tokio::spawn(async move {
+        while let Some(id) = rx.recv().await {
+            let Ok(permit) = sem.clone().acquire_owned().await else { break };
+            tokio::spawn(async move {
+                run_job(&id).await;
+                drop(permit);
+            });
+        }
+    });
+    tx
+}
+
+fn with_job<R>(id: &str, f: impl FnOnce(&mut Job) -> R) -> Option<R> {
+    JOBS.lock().ok()?.get_mut(id).map(f)
+}
+
+/// 보관 시간(JOB_RETENTION_SECS)이 지난 끝난 항목 정리 (분류 작업, 일괄 작업 공용)
+///
+/// `finished_at` 이 None 이면 아직 진행 중으로 보고 남깁니다.
+pub fn prune_finished<T>(items: &mut HashMap<String, T>, finished_at: impl Fn(&T) -> Option<DateTime<Utc>>) {
+    let retention = chrono::Duration::seconds(env_or("JOB_RETENTION_SECS", 3600i64));
+    let cutoff = Utc::now() - retention;
+    items.retain(|_, item| finished_at(item).is_none_or(|t| t > cutoff));
+}
+
+/// 분류 작업 등록 (메일이나 AI 세션이 없으면 404, 대기열이 가득 차면 503)
+///
+/// callback_url 은 내부망으로 요청이 나가지 않도록 검사합니다 (통과 못 하면 422).
+pub async fn enqueue(email_id: &str, callback_url: Option<String>, session_id: Option<String>) -> ApiResult<Job> {
+    if let Some(url) = &callback_url {
+        outbound::check_url(url, CALLBACK_HOSTS_ENV, false)
+            .await
+            .map_err(|e| ApiError::Validation(format!("callback_url 거부: {}", e)))?;
+    }
+    if store().get(email_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("이메일을 찾을 수 없음: {}", email_id)));
+    }
+    if let Some(id) = session_id.as_deref() {
+        session::provider(id)?;
+    }
+
+    let job = Job {
+        id: uuid::Uuid::new_v4().to_string(),
+        email_id: email_id.to_string(),
+        state: JobState::Queued,
+        category: None,
+        confidence: None,
+        error: None,
+        callback_url,
+        session_id,
+        created_at: Utc::now(),
+        started_at: None,
+        finished_at: None,
+    };
+    {
+        let mut jobs = JOBS.lock().map_err(|_| ApiError::Internal("작업 목록 잠금 실패".into()))?;
+        prune_finished(&mut jobs, |j| j.finished_at);
+        jobs.insert(job.id.clone(), job.clone());
+    }
+    if QUEUE.try_send(job.id.clone()).is_err() {
+        if let Ok(mut jobs) = JOBS.lock() {
+            jobs.remove(&job.id);
+        }
+        return Err(ApiError::Unavailable("분류 대기열이 가득 참".into()));
+    }
+    info!("[Jobs] 등록: {} (email={})", job.id, job.email_id);
+    Ok(job)
+}
+
+/// 작업 조회
+pub fn get(id: &str) -> Option<Job> {
+    JOBS.lock().ok()?.get(id).cloned()
+}
+
+async fn run_job(id: &str) {
+    let Some((email_id, session_id)) = with_job(id, |j| {
+        j.state = JobState::Running;
+        j.started_at = Some(Utc::now());
+        (j.email_id.clone(), j.session_id.clone())
+    }) else {
+        return;
+    };
+
+    let result = match store().get(&email_id) {
+        Ok(Some(email)) => classify_timed(&email, session_id.as_deref()).await,
+        Ok(None) => Err(ApiError::NotFound(format!("이메일을 찾을 수 없음: {}", email_id))),
+        Err(e) => Err(e.into()),
+    }
+    .and_then(|(cat, conf)| {
+        set_category(&email_id, &cat)?;
+        Ok((cat, conf))
+    });
+
+    let finished = with_job(id, |j| {
+        match &result {
+            Ok((cat, conf)) => {
+                j.state = JobState::Succeeded;
+                j.category = Some(cat.clone());
+                j.confidence = Some(*conf);
+            }
+            Err(e) => {
+                j.state = JobState::Failed;
+                j.error = Some(JobError { code: e.code(), detail: e.detail().to_string() });
+            }
+        }
+        j.finished_at = Some(Utc::now());
+        j.clone()
+    });
+    match &result {
+        Ok((cat, _)) => info!("[Jobs] 완료: {} → {}", id, cat),
+        Err(e) => {
+            warn!("[Jobs] 실패: {} — {}", id, e);
+            publish_id(EventKind::Failed, &email_id, Some(e.detail().to_string()));
+        }
+    }
+
+    if let Some(job) = finished {
+        if let Some(url) = job.callback_url.clone() {
+            send_callback(&url, &job).await;
+        }
+    }
+}
+
+/// 완료 콜백 (실패하면 1s, 2s 간격으로 재시도)
+async fn send_callback(url: &str, job: &Job) {
+    let body = match serde_json::to_vec(job) {
+        Ok(b) => b,
+        Err(e) => {
+            warn!("[Jobs] 콜백 직렬화 실패: {}", e);
+            return;
+        }
+    };
+    let signature = env::var("JOB_CALLBACK_SECRET").ok().and_then(|secret| {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+        mac.update(&body);
+        Some(format!("sha256={}", hex::encode(mac.finalize().into_bytes())))
+    });
+    // 등록 이후 DNS 가 바뀌었을 수 있으므로 보내기 직전에 다시 검사하고 그 주소로 고정
+    let target = match outbound::check_url(url, CALLBACK_HOSTS_ENV, false).await {
+        Ok(t) => t,
+        Err(e) => {
+            warn!("[Jobs] 콜백 {} 거부: {}", url, e);
+            return;
+        }
+    };
+    let client = match target.client() {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("[Jobs] 콜백 클라이언트 생성 실패: {}", e);
+            return;
+        }
+    };
+    for attempt in 1..=CALLBACK_ATTEMPTS {
+        let mut req = client
+            .post(target.url.clone())
+            .timeout(CALLBACK_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(sig) = &signature {
+            req = req.header("X-Postman-Signature", sig);
+        }
+        match req.send().await {
+            Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) => warn!("[Jobs] 콜백 {} 응답 {} (시도 {}/{})", url, resp.status(), attempt, CALLBACK_ATTEMPTS),
+            Err(e) => warn!("[Jobs] 콜백 {} 실패: {} (시도 {}/{})", url, e, attempt, CALLBACK_ATTEMPTS),
+        }
+        if attempt < CALLBACK_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+        }
+    }
+}
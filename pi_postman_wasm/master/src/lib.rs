@@ -1,6 +1,18 @@
 // master/src/lib.rs
+pub mod accounts;
 pub mod api;
 pub mod ai;
+pub mod auth;
+pub mod batch;
+pub mod config;
 pub mod email;
+pub mod error;
+pub mod events;
 pub mod gmail;
-pub mod notifier;
+pub mod inbound;
+pub mod jobs;
+pub mod outbound;
+pub mod query;
+pub mod session;
+pub mod smtp;
+pub mod status;
@@ -1,16 +1,44 @@
 // master/src/notifier.rs
 
 use chrono::Local;
+use common::attachment::attachments_for_prompt;
 use common::classifier::classify_via_openai;
 use common::discord::send_discord_alert;
-use common::gmail::{connect_to_gmail, fetch_unseen_emails, GmailConfig};
+use common::events::{publish_id, EventKind};
+use common::email::{set_category, store_incoming};
+use common::local;
+use common::source::MailSource;
+use common::store;
+use common::gmail::{
+    apply_category_label, AsyncSession, GmailConfig, LabelConfig, ParsedEmail,
+};
 use dotenv::dotenv;
-use fxhash::FxHasher;
-use std::{env, hash::Hasher, sync::Arc, time::Duration}; // Duration 추가
+use master::accounts::{load_accounts, AccountConfig, FolderConfig, FolderRule, SourceKind};
+use master::api::create_router;
+use master::smtp::{self, SmtpConfig};
+use master::status;
+use std::{collections::HashMap, env, sync::Arc, time::Duration}; // Duration 추가
 use tokio::{sync::Semaphore, task};
-use tracing::{debug, error, info}; // debug 매크로 import
+use tracing::{debug, error, info, warn}; // debug 매크로 import
 use tracing_subscriber;
 
+/// 재연결 대기 시간 (실패가 이어지면 두 배씩, 최대 MAX)
+const RECONNECT_MIN: Duration = Duration::from_secs(10);
+const RECONNECT_MAX: Duration = Duration::from_secs(300);
+
+/// 모든 계정 루프가 공유하는 설정
+struct Shared {
+    default_webhook: Option<String>,
+    worker_id: u64,
+    total: u64,
+    sem: Arc<Semaphore>,
+    concurrency: usize,
+    apply_labels: bool,
+    label_cfg: Arc<LabelConfig>,
+    attachment_budget: usize,
+    poll_interval: Duration,
+}
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
@@ -18,11 +46,8 @@ async fn main() {
         .with_max_level(tracing::Level::DEBUG)
         .init();
 
-    let cfg = GmailConfig {
-        email: env::var("GMAIL_EMAIL").expect("GMAIL_EMAIL 필요"),
-        password: env::var("GMAIL_PASSWORD").expect("GMAIL_PASSWORD 필요"),
-    };
-    let webhook = env::var("DISCORD_WEBHOOK_URL").expect("DISCORD_WEBHOOK_URL 필요");
+    // 메일 저장소 (EMAIL_DB_PATH 가 있으면 SQLite)
+    store::init_from_env().expect("메일 저장소 초기화 실패");
 
     // WORKER_ID와 TOTAL_WORKERS를 명시적으로 파싱
     let worker_id: u64 = env::var("WORKER_ID")
@@ -37,79 +62,383 @@ async fn main() {
         .ok()
         .and_then(|s| s.parse().ok())
         .unwrap_or(4);
-    let sem = Arc::new(Semaphore::new(concurrency));
 
+    // 메일 소스: gmail (기본) 또는 local (.eml / mbox / Maildir)
+    let source = env::var("MAIL_SOURCE").unwrap_or_else(|_| "gmail".to_string());
+    let local_path = match source.as_str() {
+        "gmail" => None,
+        "local" => Some(env::var("LOCAL_MAIL_PATH").expect("MAIL_SOURCE=local 이면 LOCAL_MAIL_PATH 필요")),
+        other => panic!("알 수 없는 MAIL_SOURCE: {}", other),
+    };
+
+    // 분류 결과를 Gmail 라벨로 반영 (GMAIL_APPLY_LABELS=true 일 때만, 로컬 소스는 제외)
+    let apply_labels = local_path.is_none() && env::var("GMAIL_APPLY_LABELS")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    let label_cfg = Arc::new(LabelConfig::from_env());
+    // 분류 프롬프트에 넣을 첨부 텍스트 최대 길이 (문자 수)
+    let attachment_budget: usize = env::var("ATTACHMENT_TEXT_BUDGET")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(4000);
+    let poll_secs: u64 = env::var("POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(10);
+
+    let shared = Arc::new(Shared {
+        default_webhook: env::var("DISCORD_WEBHOOK_URL").ok(),
+        worker_id,
+        total,
+        sem: Arc::new(Semaphore::new(concurrency)),
+        concurrency,
+        apply_labels,
+        label_cfg,
+        attachment_budget,
+        poll_interval: Duration::from_secs(poll_secs),
+    });
+
+    if let Some(path) = local_path {
+        run_local(&path, shared).await;
+        return;
+    }
+
+    let accounts = load_accounts().expect("계정 설정 읽기 실패");
     info!(
-        "[Notifier] shard {}/{} 시작 — 동시처리={}",
-        worker_id, total, concurrency
+        "[Notifier] shard {}/{} 시작 — 계정={} 동시처리={} 라벨={} (dry-run={})",
+        worker_id,
+        total,
+        accounts.len(),
+        concurrency,
+        apply_labels,
+        shared.label_cfg.dry_run
     );
 
+    // 같은 프로세스에서 API 제공 (API_ADDR 설정 시)
+    if let Ok(addr) = env::var("API_ADDR") {
+        task::spawn(async move {
+            match tokio::net::TcpListener::bind(&addr).await {
+                Ok(listener) => {
+                    info!("[API] {} 에서 대기", addr);
+                    if let Err(e) = axum::serve(listener, create_router()).await {
+                        error!("[API] 서버 종료: {}", e);
+                    }
+                }
+                Err(e) => error!("[API] {} 바인드 실패: {}", addr, e),
+            }
+        });
+    }
+
+    // 내장 SMTP 수신 서버 (SMTP_ADDR 설정 시)
+    match SmtpConfig::from_env() {
+        Ok(Some(cfg)) => {
+            task::spawn(async move {
+                if let Err(e) = smtp::serve(cfg).await {
+                    error!("[SMTP] 서버 종료: {}", e);
+                }
+            });
+        }
+        Ok(None) => {}
+        Err(e) => error!("[SMTP] 설정 오류: {}", e),
+    }
+
+    // 계정마다 독립적인 재연결 루프
+    let mut handles = Vec::new();
+    for account in accounts {
+        let folders: Vec<String> = account.folders.iter().map(|f| f.display_name()).collect();
+        status::register(&account.name, &account.email, &folders);
+        handles.push(task::spawn(run_account(account, shared.clone())));
+    }
+    for h in handles {
+        if let Err(e) = h.await {
+            error!("[Notifier] 계정 루프 비정상 종료: {}", e);
+        }
+    }
+}
+
+/// 계정 하나를 주기적으로 확인 (연결 실패 시 지수 백오프)
+async fn run_account(account: AccountConfig, shared: Arc<Shared>) {
+    match account.mail_source() {
+        Ok(Some(source)) => return run_source(account, source, shared).await,
+        Ok(None) => {}
+        Err(e) => {
+            error!("[{}] 메일 소스 설정 오류: {}", account.name, e);
+            status::record_error(&account.name, &format!("소스 설정 오류: {}", e));
+            return;
+        }
+    }
+    let gmail = account.gmail();
+    let mut backoff = RECONNECT_MIN;
+
     loop {
         info!(
-            "[Notifier] [{}] UNSEEN 메일 확인 중…",
+            "[{}] [{}] UNSEEN 메일 확인 중…",
+            account.name,
             Local::now().format("%Y-%m-%d %H:%M:%S")
         );
+        status::update(&account.name, |s| s.state = "connecting".to_string());
 
-        // Gmail 연결 및 메일 fetch
-        let mails = match connect_to_gmail(&cfg)
-            .await
-            .and_then(|mut s| fetch_unseen_emails(&mut s))
-        {
-            Ok(v) => v,
+        let mut session = match AsyncSession::connect(&gmail).await {
+            Ok(s) => s,
             Err(e) => {
-                error!("[Gmail] 연결/조회 실패: {}", e);
-                tokio::time::sleep(Duration::from_secs(10)).await;
+                error!("[{}] Gmail 연결 실패: {} ({}초 후 재시도)", account.name, e, backoff.as_secs());
+                status::record_error(&account.name, &format!("연결 실패: {}", e));
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX);
                 continue;
             }
         };
-        info!("[Notifier] 발견된 메일 수: {}", mails.len());
-
-        for em in mails {
-            // (1) UID 문자열을 u64 숫자로 파싱
-            let uid_num: u64 = em
-                .uid
-                .parse()
-                .expect(&format!("UID `{}`를 u64로 파싱할 수 없습니다", em.uid));
-
-            // (2) total 개수로 나눈 나머지를 곧 h로 사용
-            let h = uid_num % total;
-            info!(
-                "[Shard] numeric-shard: {} % {} = {} (내 id={})",
-                uid_num, total, h, worker_id
-            );
-
-            if h != worker_id {
-                debug!("[Shard] 스킵됨 uid={}", em.uid);
+        backoff = RECONNECT_MIN;
+        status::update(&account.name, |s| s.state = "polling".to_string());
+
+        let mut fetched = 0;
+        let mut failed = false;
+        for folder in &account.folders {
+            // 가져오기만 해도 \Seen 이 붙으므로 무시 폴더는 아예 조회하지 않음
+            if folder.rule == FolderRule::Ignore {
+                debug!("[{}] {} 무시 규칙 — 조회 생략", account.name, folder.display_name());
                 continue;
             }
+            let name = folder.display_name();
+            let query = folder.gmail_query();
+            // 메일마다 가져와서 바로 저장하고, 저장된 메일만 읽음 표시
+            let result = session
+                .for_each_unseen(
+                    &folder.mailbox,
+                    query.as_deref(),
+                    |uid| owns_shard(&shared, uid as u64),
+                    |em| dispatch(&account, folder, em, &shared),
+                )
+                .await;
+            match result {
+                Ok(count) => {
+                    info!("[{}] {} 처리한 메일 수: {}", account.name, name, count);
+                    fetched += count;
+                }
+                Err(e) => {
+                    error!("[{}] {} 조회 실패: {}", account.name, name, e);
+                    status::record_error(&account.name, &format!("{} 조회 실패: {}", name, e));
+                    failed = true;
+                    // 세션이 버려졌으면 남은 폴더는 다음 확인에서
+                    if !session.is_connected() {
+                        break;
+                    }
+                }
+            }
+        }
+        session.logout().await;
+        if !failed {
+            status::record_poll(&account.name, fetched);
+        }
 
-            let permit = sem.clone().acquire_owned().await.unwrap();
-            let hook = webhook.clone();
-            let subj = em.subject.clone();
-            let sndr = em.from.clone();
-            let body = em.body.clone();
+        tokio::time::sleep(shared.poll_interval).await;
+    }
+}
 
-            task::spawn(async move {
-                info!(
-                    "[{}] 분류 시작: {}",
-                    Local::now().format("%Y-%m-%d %H:%M:%S"),
-                    subj
-                );
-                match classify_via_openai(&subj, &body).await {
-                    Ok((cat, score)) => {
-                        info!("[AI] 분류 완료: {} ({})", cat, score);
-                        if let Err(e) = send_discord_alert(&hook, &subj, &sndr, &cat).await {
-                            error!("[Discord] 전송 실패: {}", e);
-                        }
+/// POP3 / JMAP 소스 확인 루프 (첫 번째 폴더 규칙 적용)
+async fn run_source(account: AccountConfig, mut source: MailSource, shared: Arc<Shared>) {
+    let folder = account.folders[0].clone();
+    if folder.rule == FolderRule::Ignore {
+        warn!("[{}] 무시 규칙 — {} 소스를 확인하지 않음", account.name, source.kind());
+        return;
+    }
+    let mut backoff = RECONNECT_MIN;
+    info!("[{}] {} 소스로 확인", account.name, source.kind());
+
+    loop {
+        status::update(&account.name, |s| s.state = "polling".to_string());
+        match source.fetch_new().await {
+            Ok(mails) => {
+                backoff = RECONNECT_MIN;
+                info!("[{}] 발견된 메일 수: {}", account.name, mails.len());
+                let count = mails.len();
+                let mut stored = Vec::new();
+                for em in mails {
+                    let uid = em.uid.clone();
+                    if dispatch(&account, &folder, em, &shared).await {
+                        stored.push(uid);
+                    }
+                }
+                if let Err(e) = source.ack(&stored).await {
+                    warn!("[{}] 처리 완료 표시 실패 (다음 확인에서 다시 가져옴): {}", account.name, e);
+                }
+                status::record_poll(&account.name, count);
+                source.wait(shared.poll_interval).await;
+            }
+            Err(e) => {
+                error!("[{}] {} 조회 실패: {} ({}초 후 재시도)", account.name, source.kind(), e, backoff.as_secs());
+                status::record_error(&account.name, &format!("{} 조회 실패: {}", source.kind(), e));
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX);
+            }
+        }
+    }
+}
+
+/// 로컬 메일 원본을 한 번 처리하고 종료
+async fn run_local(path: &str, shared: Arc<Shared>) {
+    info!(
+        "[Local] {} 처리 시작 — shard {}/{} 동시처리={}",
+        path, shared.worker_id, shared.total, shared.concurrency
+    );
+    let mails = match local::load_path(path) {
+        Ok(m) => m,
+        Err(e) => {
+            error!("[Local] 읽기 실패: {}", e);
+            return;
+        }
+    };
+    info!("[Local] 읽은 메일 수: {}", mails.len());
+
+    let account = AccountConfig {
+        name: "local".to_string(),
+        email: path.to_string(),
+        password: String::new(),
+        folders: vec![FolderConfig::from(path.to_string())],
+        webhook_url: None,
+        routes: HashMap::new(),
+        source: Default::default(),
+    };
+    status::register(&account.name, &account.email, &[path.to_string()]);
+    let folder = account.folders[0].clone();
+    let count = mails.len();
+    for em in mails {
+        dispatch(&account, &folder, em, &shared).await;
+    }
+
+    // 진행 중인 분류 작업이 모두 끝날 때까지 대기
+    let _all = shared.sem.acquire_many(shared.concurrency as u32).await;
+    status::record_poll(&account.name, count);
+    info!("[Local] 처리 완료");
+}
+
+/// 이 워커가 맡은 UID 인지 (UID % TOTAL_WORKERS == WORKER_ID)
+fn owns_shard(shared: &Shared, uid_num: u64) -> bool {
+    let h = uid_num % shared.total;
+    debug!(
+        "[Shard] numeric-shard: {} % {} = {} (내 id={})",
+        uid_num, shared.total, h, shared.worker_id
+    );
+    h == shared.worker_id
+}
+
+/// 샤딩 확인 후 저장하고 분류/알림 작업 생성
+///
+/// 저장까지 끝났으면 true (그때만 원본 메일함에 읽음 표시해도 됨)
+async fn dispatch(account: &AccountConfig, folder: &FolderConfig, em: ParsedEmail, shared: &Arc<Shared>) -> bool {
+    // 무시 폴더는 조회 단계에서 건너뛰므로 여기까지 오지 않음 (방어용)
+    if folder.rule == FolderRule::Ignore {
+        debug!("[{}] {} 무시 규칙 — uid={}", account.name, folder.display_name(), em.uid);
+        return false;
+    }
+
+    // UID 문자열을 u64 숫자로 파싱 (POP3 UIDL / JMAP id 처럼 숫자가 아니면 해시)
+    let uid_num = em.uid.parse::<u64>().unwrap_or_else(|_| fxhash::hash64(em.uid.as_bytes()));
+    if !owns_shard(shared, uid_num) {
+        debug!("[Shard] 스킵됨 uid={}", em.uid);
+        return false;
+    }
+
+    let permit = shared.sem.clone().acquire_owned().await.unwrap();
+    // 분류 전에 먼저 저장 (분류에 실패해도 메일은 남김, 저장 실패면 읽음 표시하지 않고 다음에 재시도)
+    let email_id = match store_incoming(em.to_email()).await {
+        Ok(id) => id,
+        Err(e) => {
+            error!("[{}] 메일 저장 실패: {}", account.name, e);
+            return false;
+        }
+    };
+    let mut body = match em.headers.summary() {
+        s if s.is_empty() => em.body.clone(),
+        s => format!("{}\n\n{}", s, em.body),
+    };
+    let attachment_text = attachments_for_prompt(&em.attachments, shared.attachment_budget);
+    if !attachment_text.is_empty() {
+        body = format!("{}\n\n{}", body, attachment_text);
+    }
+    let account = account.clone();
+    let mailbox = folder.mailbox.clone();
+    let rule = folder.rule;
+    let shared = shared.clone();
+
+    task::spawn(async move {
+        info!(
+            "[{}] [{}] 분류 시작: {}",
+            account.name,
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            em.subject
+        );
+        match classify_via_openai(&em.subject, &body).await {
+            Ok((cat, score)) => {
+                info!("[{}] 분류 완료: {} ({})", account.name, cat, score);
+                if let Err(e) = set_category(&email_id, &cat) {
+                    error!("[{}] 분류 결과 저장 실패: {}", account.name, e);
+                }
+                // 라벨은 IMAP 계정에만 적용
+                if shared.apply_labels && matches!(account.source, SourceKind::Imap) {
+                    label_email(&account.gmail(), mailbox, em.uid.clone(), cat.clone(), shared.label_cfg.clone())
+                        .await;
+                }
+                let is_spam = cat.eq_ignore_ascii_case("SPAM");
+                let subject = match rule {
+                    FolderRule::Silent => {
+                        debug!("[{}] 알림 없음 규칙 — {}", account.name, em.subject);
+                        return;
+                    }
+                    FolderRule::Audit if is_spam => {
+                        debug!("[{}] 스팸함 감사: 정상 스팸 — {}", account.name, em.subject);
+                        return;
                     }
-                    Err(e) => {
-                        error!("[AI] 분류 실패: {}", e);
+                    FolderRule::Audit => format!("[스팸함 오탐 의심] {}", em.subject),
+                    _ => em.subject.clone(),
+                };
+                match account.webhook_for(&cat, shared.default_webhook.as_deref()) {
+                    Some(hook) => {
+                        match send_discord_alert(hook, &subject, &em.from, &cat, Some(&em.gmail_link)).await {
+                            Ok(()) => publish_id(EventKind::Notified, &email_id, None),
+                            Err(e) => {
+                                error!("[{}] Discord 전송 실패: {}", account.name, e);
+                                publish_id(EventKind::Failed, &email_id, Some(format!("Discord 전송 실패: {}", e)));
+                            }
+                        }
                     }
+                    None => warn!("[{}] 카테고리 {} 에 대한 웹훅 없음 — 알림 생략", account.name, cat),
                 }
-                drop(permit);
-            });
+            }
+            Err(e) => {
+                error!("[{}] AI 분류 실패: {}", account.name, e);
+                publish_id(EventKind::Failed, &email_id, Some(format!("AI 분류 실패: {}", e)));
+            }
         }
+        drop(permit);
+    });
+    true
+}
 
-        tokio::time::sleep(Duration::from_secs(10)).await;
+/// 별도 세션으로 메일함을 열어 분류 라벨을 적용
+async fn label_email(
+    cfg: &GmailConfig,
+    folder: String,
+    uid: String,
+    category: String,
+    labels: Arc<LabelConfig>,
+) {
+    let mut session = match AsyncSession::connect(cfg).await {
+        Ok(s) => s,
+        Err(e) => {
+            error!("[Label] Gmail 연결 실패: {}", e);
+            return;
+        }
+    };
+    let target = uid.clone();
+    let result = session
+        .run(move |s| {
+            s.select(&folder)?;
+            apply_category_label(s, &target, &category, &labels)
+        })
+        .await;
+    if let Err(e) = result {
+        error!("[Label] UID {} 라벨 적용 실패: {}", uid, e);
     }
+    session.logout().await;
 }
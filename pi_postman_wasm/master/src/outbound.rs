@@ -0,0 +1,112 @@
+// master/src/outbound.rs
+//
+// 요청자가 준 URL 로 서버가 직접 요청을 보낼 때의 대상 검사 (SSRF 방지)
+// 허용 목록 환경 변수가 있으면 그 호스트만, 없으면 호스트를 조회해
+// 사설/루프백/링크 로컬 등 내부 주소로 가는 요청을 막습니다.
+
+use reqwest::{redirect, Client, Url};
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+
+/// 검사를 통과한 요청 대상
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub url: Url,
+    host: String,
+    /// 검사한 주소 (요청 때 이 주소로 고정해 DNS 재바인딩을 막음). 허용 목록으로 통과했으면 비어 있음
+    addrs: Vec<SocketAddr>,
+}
+
+impl Target {
+    /// 검사한 주소로만 연결하고 리다이렉트는 따르지 않는 클라이언트
+    pub fn client(&self) -> reqwest::Result<Client> {
+        let mut builder = Client::builder().redirect(redirect::Policy::none());
+        if let Some(addr) = self.addrs.first() {
+            builder = builder.resolve(&self.host, *addr);
+        }
+        builder.build()
+    }
+}
+
+/// URL 검사 후 연결할 주소 조회
+///
+/// `allow_env` 에 쉼표로 구분한 호스트 목록이 있으면 목록에 있는 호스트만 허용합니다
+/// (".example.com" 처럼 점으로 시작하면 하위 도메인 포함). 목록이 없으면 조회된 주소가
+/// 모두 공인 주소여야 합니다. `https_only` 면 http:// 는 거부합니다.
+pub async fn check_url(raw: &str, allow_env: &str, https_only: bool) -> Result<Target, String> {
+    let url = Url::parse(raw).map_err(|e| format!("URL 형식 오류: {} ({})", raw, e))?;
+    match url.scheme() {
+        "https" => {}
+        "http" if !https_only => {}
+        "http" => return Err(format!("https URL 만 허용: {}", raw)),
+        other => return Err(format!("지원하지 않는 스킴: {}", other)),
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("URL 에 사용자 정보를 넣을 수 없음".into());
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("호스트가 없는 URL: {}", raw))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase();
+
+    if let Ok(list) = env::var(allow_env) {
+        if !host_allowed(&host, &list) {
+            return Err(format!("허용되지 않은 호스트: {} ({} 확인)", host, allow_env));
+        }
+        return Ok(Target { url, host, addrs: Vec::new() });
+    }
+
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| format!("{} 주소 조회 실패: {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{} 주소 조회 결과 없음", host));
+    }
+    if let Some(addr) = addrs.iter().find(|a| !is_public(a.ip())) {
+        return Err(format!("내부 주소로는 요청할 수 없음: {} → {}", host, addr.ip()));
+    }
+    Ok(Target { url, host, addrs })
+}
+
+fn host_allowed(host: &str, list: &str) -> bool {
+    list.split(',')
+        .map(|h| h.trim().to_ascii_lowercase())
+        .filter(|h| !h.is_empty())
+        .any(|h| match h.strip_prefix('.') {
+            Some(domain) => host == domain || host.ends_with(&h),
+            None => host == h,
+        })
+}
+
+/// 인터넷에서 라우팅되는 주소인지 (사설/루프백/링크 로컬/CGNAT/문서용/멀티캐스트 제외)
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
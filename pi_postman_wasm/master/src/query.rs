@@ -0,0 +1,250 @@
+// master/src/query.rs
+//
+// POST /api/ai/query — 자유 질문에 답변 (email_id 를 주면 그 메일, include_thread 면 스레드까지 맥락으로 넣음)
+//
+// stream: true 이거나 Accept: text/event-stream 이면 SSE 로 조각을 바로 보냅니다.
+//   event: delta  data: {"text": "..."}
+//   event: done   data: {"finish_reason": "stop"}
+//   event: error  data: problem+json 과 같은 형식
+//
+// 환경 변수
+//   QUERY_CONTEXT_CHARS  맥락으로 넣을 메일 본문 총 글자 수 (기본 12000)
+//   QUERY_THREAD_LIMIT   스레드에서 가져올 최대 메일 수 (기본 10)
+
+use crate::config::env_or;
+use crate::error::{current_request_id, ApiError, ApiJson, ApiResult};
+use crate::session::{self, AiProvider};
+use axum::{
+    http::{header, HeaderMap},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use common::classifier::OpenAiConfig;
+use common::email::Email;
+use common::store::{store, thread_of};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+const SYSTEM_PROMPT: &str = "당신은 사용자의 이메일 비서입니다. \
+     맥락으로 주어진 이메일이 있으면 그 내용을 근거로 답하고, 이메일에 없는 내용은 추측하지 말고 모른다고 답하세요. \
+     답변은 질문과 같은 언어로 간결하게 작성하세요.";
+
+#[derive(Deserialize)]
+pub struct AiQueryRequest {
+    /// 없으면 서버 환경 변수 설정
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub prompt: String,
+    #[serde(default)]
+    pub email_id: Option<String>,
+    /// 같은 스레드의 메일도 맥락에 포함
+    #[serde(default)]
+    pub include_thread: bool,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Serialize)]
+pub struct AiQueryResponse {
+    pub success: bool,
+    pub answer: String,
+    pub model: String,
+    /// 맥락으로 사용한 메일 ID
+    pub context_email_ids: Vec<String>,
+}
+
+/// 맥락에 넣을 메일 (요청한 메일 + 스레드)
+fn context_emails(req: &AiQueryRequest) -> ApiResult<Vec<Email>> {
+    let Some(id) = req.email_id.as_deref().filter(|s| !s.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let store = store();
+    let email = store
+        .get(id)?
+        .ok_or_else(|| ApiError::NotFound(format!("이메일을 찾을 수 없음: {}", id)))?;
+    let mut emails = if req.include_thread {
+        thread_of(store.as_ref(), &email, env_or("QUERY_THREAD_LIMIT", 10))?
+    } else {
+        Vec::new()
+    };
+    emails.push(email);
+    Ok(emails)
+}
+
+/// 메일 목록을 프롬프트용 텍스트로 (본문은 글자 수 예산을 메일마다 같은 몫으로 나눔)
+fn render_context(emails: &[Email], budget: usize) -> String {
+    let per_email = budget / emails.len().max(1);
+    emails
+        .iter()
+        .map(|e| {
+            let mut body: String = e.body.chars().take(per_email).collect();
+            if body.len() < e.body.len() {
+                body.push_str("\n…(생략)");
+            }
+            format!(
+                "[이메일 {}]\n보낸이: {}\n받는이: {}\n제목: {}\n수신: {}\n분류: {}\n본문:\n{}",
+                e.id,
+                e.from,
+                e.to,
+                e.subject,
+                e.received_at.to_rfc3339(),
+                e.category.as_deref().unwrap_or("-"),
+                body
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn messages(emails: &[Email], prompt: &str) -> Value {
+    let user = if emails.is_empty() {
+        prompt.to_string()
+    } else {
+        format!(
+            "다음은 참고할 이메일입니다 (오래된 순).\n\n{}\n\n질문:\n{}",
+            render_context(emails, env_or("QUERY_CONTEXT_CHARS", 12000)),
+            prompt
+        )
+    };
+    json!([
+        { "role": "system", "content": SYSTEM_PROMPT },
+        { "role": "user", "content": user },
+    ])
+}
+
+fn upstream_error(config: &OpenAiConfig, e: reqwest::Error) -> ApiError {
+    if e.is_timeout() {
+        ApiError::UpstreamTimeout(format!("AI 응답 없음 ({}초)", config.timeout.as_secs()))
+    } else {
+        ApiError::Upstream(format!("AI 호출 실패: {}", e))
+    }
+}
+
+/// chat/completions 호출 (응답 상태까지 확인)
+async fn send(provider: &AiProvider, messages: Value, stream: bool) -> ApiResult<reqwest::Response> {
+    let config = &provider.config;
+    let body = json!({ "model": config.model, "messages": messages, "stream": stream });
+    let mut req = provider
+        .http
+        .post(format!("{}/chat/completions", config.base()))
+        .bearer_auth(&config.api_key)
+        .json(&body);
+    // 스트리밍은 전체 시간이 아니라 조각 사이 간격으로 제한
+    if !stream {
+        req = req.timeout(config.timeout);
+    }
+    let resp = tokio::time::timeout(config.timeout, req.send())
+        .await
+        .map_err(|_| ApiError::UpstreamTimeout(format!("AI 응답 없음 ({}초)", config.timeout.as_secs())))?
+        .map_err(|e| upstream_error(config, e))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(ApiError::Upstream(format!("AI 응답 {}: {}", status, text)));
+    }
+    Ok(resp)
+}
+
+fn wants_stream(req: &AiQueryRequest, headers: &HeaderMap) -> bool {
+    req.stream
+        || headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|a| a.contains("text/event-stream"))
+}
+
+/// 이메일 맥락을 넣어 AI 에 질문
+pub async fn query_ai(headers: HeaderMap, ApiJson(req): ApiJson<AiQueryRequest>) -> ApiResult<Response> {
+    if req.prompt.trim().is_empty() {
+        return Err(ApiError::Validation("prompt 는 비어 있을 수 없음".into()));
+    }
+    let provider = session::resolve(req.session_id.as_deref())?;
+    let config = provider.config.clone();
+    let emails = context_emails(&req)?;
+    let context_email_ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
+    let stream = wants_stream(&req, &headers);
+    info!("[AI] 질의 model={} 맥락={}건 stream={}", config.model, context_email_ids.len(), stream);
+
+    let resp = send(&provider, messages(&emails, &req.prompt), stream).await?;
+    if stream {
+        return Ok(stream_answer(resp, config).into_response());
+    }
+
+    let body: Value = resp.json().await.map_err(|e| upstream_error(&config, e))?;
+    let answer = body["choices"][0]["message"]["content"].as_str().unwrap_or_default().to_string();
+    Ok(Json(AiQueryResponse { success: true, answer, model: config.model, context_email_ids }).into_response())
+}
+
+/// 제공자 SSE(data: {...} 줄)를 읽어 delta/done/error 이벤트로 다시 보냄
+fn stream_answer(mut resp: reqwest::Response, config: OpenAiConfig) -> Sse<ReceiverStream<Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel(32);
+    // 요청 ID 는 task-local 이라 새 태스크로 넘겨 줌
+    let request_id = current_request_id();
+    tokio::spawn(async move {
+        let mut buf: Vec<u8> = Vec::new();
+        let mut finish_reason: Option<String> = None;
+        loop {
+            let chunk = match tokio::time::timeout(config.timeout, resp.chunk()).await {
+                Ok(Ok(Some(chunk))) => chunk,
+                Ok(Ok(None)) => break,
+                Ok(Err(e)) => {
+                    send_error(&tx, upstream_error(&config, e), request_id).await;
+                    return;
+                }
+                Err(_) => {
+                    let secs = config.timeout.as_secs();
+                    let e = ApiError::UpstreamTimeout(format!("AI 스트림 응답 없음 ({}초)", secs));
+                    send_error(&tx, e, request_id).await;
+                    return;
+                }
+            };
+            buf.extend_from_slice(&chunk);
+            while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else { continue };
+                if data == "[DONE]" {
+                    let _ = tx.send(Ok(done_event(finish_reason.as_deref()))).await;
+                    return;
+                }
+                let Ok(v) = serde_json::from_str::<Value>(data) else {
+                    warn!("[AI] 스트림 조각 파싱 실패: {}", data);
+                    continue;
+                };
+                if let Some(reason) = v["choices"][0]["finish_reason"].as_str() {
+                    finish_reason = Some(reason.to_string());
+                }
+                if let Some(text) = v["choices"][0]["delta"]["content"].as_str().filter(|t| !t.is_empty()) {
+                    let event = Event::default().event("delta").data(json!({ "text": text }).to_string());
+                    // 클라이언트가 끊으면 중단
+                    if tx.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = tx.send(Ok(done_event(finish_reason.as_deref()))).await;
+    });
+    Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default())
+}
+
+fn done_event(finish_reason: Option<&str>) -> Event {
+    Event::default()
+        .event("done")
+        .data(json!({ "finish_reason": finish_reason }).to_string())
+}
+
+async fn send_error(tx: &mpsc::Sender<Result<Event, Infallible>>, e: ApiError, request_id: Option<String>) {
+    warn!("[AI] 스트림 오류: {}", e);
+    let mut problem = e.to_problem();
+    problem.request_id = request_id;
+    let data = serde_json::to_string(&problem).unwrap_or_default();
+    let _ = tx.send(Ok(Event::default().event("error").data(data))).await;
+}
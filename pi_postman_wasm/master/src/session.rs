@@ -0,0 +1,200 @@
+// master/src/session.rs
+//
+// AI 세션: 클라이언트가 /api/ai/connect 로 보낸 제공자 설정(키, 모델, 주소, 타임아웃)을
+// 서버 메모리에 보관하고 세션 ID 로 분류/질의 요청에서 사용합니다.
+// 키는 응답이나 로그에 나가지 않고, 마지막 사용 후 AI_SESSION_TTL_SECS (기본 3600) 가 지나면 만료됩니다.
+//
+// base_url 은 https 공인 주소만 받습니다. 내부 게이트웨이(로컬 모델 등)를 쓰려면
+// AI_BASE_URL_HOSTS 에 호스트를 나열합니다 (목록에 있는 호스트는 http 도 허용).
+// 세션의 모든 제공자 요청은 connect 때 검사한 주소에 고정된 클라이언트로 보냅니다.
+// 세션 없이 환경 변수 설정으로 분류할 때는 AI_TIMEOUT_SECS 가 있으면 그 값을 타임아웃으로 씁니다.
+
+use crate::config::env_or;
+use crate::error::{ApiError, ApiResult};
+use crate::outbound;
+use chrono::{DateTime, Utc};
+use common::classifier::OpenAiConfig;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+/// 연결 확인 요청 타임아웃
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 세션별 타임아웃 상한 (초)
+const MAX_TIMEOUT_SECS: u64 = 300;
+/// base_url 허용 호스트 목록 환경 변수
+const BASE_URL_HOSTS_ENV: &str = "AI_BASE_URL_HOSTS";
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, AiSession>> = Mutex::new(HashMap::new());
+}
+
+struct AiSession {
+    provider: AiProvider,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+/// 분류/질의/일괄 처리에 쓸 제공자 설정과 HTTP 클라이언트
+///
+/// 세션 클라이언트는 검사한 주소로만 연결하고 리다이렉트를 따르지 않으므로, 이후 DNS 가
+/// 내부 주소로 바뀌어도 키와 메일 내용이 그쪽으로 가지 않습니다.
+#[derive(Clone)]
+pub struct AiProvider {
+    pub config: OpenAiConfig,
+    pub http: reqwest::Client,
+}
+
+/// 세션 정보 (키 제외)
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub model: String,
+    pub base_url: String,
+    pub timeout_secs: u64,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+fn ttl() -> chrono::Duration {
+    let secs = env_or("AI_SESSION_TTL_SECS", 3600i64);
+    chrono::Duration::seconds(secs.max(60))
+}
+
+fn info_of(id: &str, s: &AiSession) -> SessionInfo {
+    let config = &s.provider.config;
+    SessionInfo {
+        session_id: id.to_string(),
+        model: config.model.clone(),
+        base_url: config.base_url.clone(),
+        timeout_secs: config.timeout.as_secs(),
+        created_at: s.created_at,
+        expires_at: s.expires_at,
+    }
+}
+
+/// 저렴한 호출(GET /models/{model})로 키와 모델 확인
+///
+/// 키가 거부되면 요청한 쪽의 인증 문제가 아니라 입력값 문제이므로 422.
+async fn validate(provider: &AiProvider) -> ApiResult<()> {
+    let config = &provider.config;
+    let url = format!("{}/models/{}", config.base(), config.model);
+    let resp = provider
+        .http
+        .get(&url)
+        .bearer_auth(&config.api_key)
+        .timeout(VALIDATE_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                ApiError::UpstreamTimeout(format!("AI 제공자 응답 없음: {}", config.base()))
+            } else {
+                ApiError::Upstream(format!("AI 제공자 연결 실패: {}", e))
+            }
+        })?;
+    match resp.status() {
+        s if s.is_success() => Ok(()),
+        s @ (reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN) => {
+            Err(ApiError::Validation(format!("AI 제공자가 API 키를 거부함 ({})", s.as_u16())))
+        }
+        reqwest::StatusCode::NOT_FOUND => Err(ApiError::Validation(format!("모델을 찾을 수 없음: {}", config.model))),
+        s => Err(ApiError::Upstream(format!("AI 제공자 응답 {}", s))),
+    }
+}
+
+/// 설정 확인 후 세션 생성
+pub async fn connect(
+    api_key: &str,
+    model: &str,
+    base_url: Option<&str>,
+    timeout_secs: Option<u64>,
+) -> ApiResult<SessionInfo> {
+    let api_key = api_key.trim();
+    let model = model.trim();
+    if api_key.is_empty() || model.is_empty() {
+        return Err(ApiError::Validation("api_key, model 은 비어 있을 수 없음".into()));
+    }
+    let base_url = base_url
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .unwrap_or("https://api.openai.com/v1");
+    // 키가 평문으로 나가거나 내부망으로 요청이 가지 않도록 (허용 목록에 있는 호스트만 예외)
+    let https_only = env::var(BASE_URL_HOSTS_ENV).is_err();
+    let target = outbound::check_url(base_url, BASE_URL_HOSTS_ENV, https_only)
+        .await
+        .map_err(|e| ApiError::Validation(format!("base_url 거부: {}", e)))?;
+    let timeout_secs = timeout_secs.unwrap_or(60);
+    if timeout_secs == 0 || timeout_secs > MAX_TIMEOUT_SECS {
+        return Err(ApiError::Validation(format!("timeout_secs 는 1..={} 이어야 함", MAX_TIMEOUT_SECS)));
+    }
+
+    let config = OpenAiConfig {
+        api_key: api_key.to_string(),
+        model: model.to_string(),
+        base_url: base_url.to_string(),
+        timeout: Duration::from_secs(timeout_secs),
+    };
+    let http = target
+        .client()
+        .map_err(|e| ApiError::Internal(format!("HTTP 클라이언트 생성 실패: {}", e)))?;
+    let provider = AiProvider { config, http };
+    validate(&provider).await?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let session = AiSession { provider, created_at: now, expires_at: now + ttl() };
+    let info = info_of(&id, &session);
+    let mut sessions = SESSIONS.lock().map_err(|_| ApiError::Internal("AI 세션 잠금 실패".into()))?;
+    sessions.retain(|_, s| s.expires_at > now);
+    sessions.insert(id.clone(), session);
+    info!("[AI] 세션 생성: {} (model={}, base={})", id, info.model, info.base_url);
+    Ok(info)
+}
+
+/// 세션 제공자 조회 (사용할 때마다 만료 시각 연장)
+pub fn provider(id: &str) -> ApiResult<AiProvider> {
+    let now = Utc::now();
+    let mut sessions = SESSIONS.lock().map_err(|_| ApiError::Internal("AI 세션 잠금 실패".into()))?;
+    match sessions.get_mut(id) {
+        Some(s) if s.expires_at > now => {
+            s.expires_at = now + ttl();
+            Ok(s.provider.clone())
+        }
+        Some(_) => {
+            sessions.remove(id);
+            Err(ApiError::NotFound(format!("AI 세션이 만료됨: {}", id)))
+        }
+        None => Err(ApiError::NotFound(format!("AI 세션을 찾을 수 없음: {}", id))),
+    }
+}
+
+/// 세션이 있으면 세션 제공자, 없으면 환경 변수 설정 (AI_TIMEOUT_SECS 가 있으면 타임아웃 대체)
+///
+/// 환경 변수 주소는 운영자가 정한 값이므로 일반 클라이언트를 씁니다.
+pub fn resolve(session_id: Option<&str>) -> ApiResult<AiProvider> {
+    match session_id.filter(|s| !s.is_empty()) {
+        Some(id) => provider(id),
+        None => {
+            let mut config = OpenAiConfig::from_env().map_err(|e| ApiError::Conflict(e.to_string()))?;
+            if let Some(secs) = env::var("AI_TIMEOUT_SECS").ok().and_then(|s| s.parse::<u64>().ok()) {
+                config.timeout = Duration::from_secs(secs.max(1));
+            }
+            Ok(AiProvider { config, http: reqwest::Client::new() })
+        }
+    }
+}
+
+/// 세션 정보 조회 (키 제외)
+pub fn get(id: &str) -> Option<SessionInfo> {
+    let sessions = SESSIONS.lock().ok()?;
+    sessions.get(id).filter(|s| s.expires_at > Utc::now()).map(|s| info_of(id, s))
+}
+
+/// 세션 삭제
+pub fn remove(id: &str) -> bool {
+    SESSIONS.lock().is_ok_and(|mut s| s.remove(id).is_some())
+}
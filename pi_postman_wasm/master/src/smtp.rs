@@ -0,0 +1,374 @@
+// master/src/smtp.rs
+//
+// 내장 SMTP 수신 서버 (EHLO, STARTTLS, SIZE, 8BITMIME)
+// MX 나 릴레이가 직접 이 서버로 메일을 넘기면 폴링 없이 분류 파이프라인에 들어갑니다.
+
+use crate::config::env_or;
+use crate::email::ingest_parsed;
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use common::gmail::parse_single_email;
+use std::{env, fs, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_native_tls::TlsAcceptor;
+use tracing::{debug, error, info, warn};
+
+/// 명령 하나를 기다리는 최대 시간
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+/// 메일 한 통의 최대 수신자 수
+const MAX_RECIPIENTS: usize = 100;
+/// 명령 줄 최대 길이 (RFC 5321: 512, 확장 파라미터 여유 포함)
+const MAX_COMMAND_LINE: usize = 4096;
+
+#[derive(Clone)]
+pub struct SmtpConfig {
+    pub addr: String,
+    /// 인사말/EHLO 응답에 쓸 호스트 이름
+    pub hostname: String,
+    /// 받을 수신자 도메인 (소문자). 이 외의 도메인은 릴레이 거부
+    pub domains: Vec<String>,
+    pub max_size: usize,
+    /// STARTTLS 용 인증서 (없으면 STARTTLS 미제공)
+    pub tls: Option<TlsAcceptor>,
+}
+
+impl SmtpConfig {
+    /// SMTP_ADDR, SMTP_HOSTNAME, SMTP_DOMAINS(쉼표 구분), SMTP_MAX_SIZE,
+    /// SMTP_TLS_CERT / SMTP_TLS_KEY (PEM 파일 경로)
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(addr) = env::var("SMTP_ADDR") else {
+            return Ok(None);
+        };
+        let domains: Vec<String> = env::var("SMTP_DOMAINS")
+            .map_err(|_| anyhow!("SMTP_ADDR 를 쓰려면 SMTP_DOMAINS 필요"))?
+            .split(',')
+            .map(|d| d.trim().trim_start_matches('@').to_ascii_lowercase())
+            .filter(|d| !d.is_empty())
+            .collect();
+        if domains.is_empty() {
+            return Err(anyhow!("SMTP_DOMAINS 가 비어 있습니다"));
+        }
+
+        let tls = match (env::var("SMTP_TLS_CERT"), env::var("SMTP_TLS_KEY")) {
+            (Ok(cert), Ok(key)) => {
+                let cert = fs::read(&cert).with_context(|| format!("인증서 읽기 실패: {}", cert))?;
+                let key = fs::read(&key).with_context(|| format!("개인키 읽기 실패: {}", key))?;
+                let identity = native_tls::Identity::from_pkcs8(&cert, &key).context("인증서/개인키 형식 오류")?;
+                let acceptor = native_tls::TlsAcceptor::new(identity).context("TLS 설정 실패")?;
+                Some(TlsAcceptor::from(acceptor))
+            }
+            _ => None,
+        };
+
+        Ok(Some(SmtpConfig {
+            addr,
+            hostname: env::var("SMTP_HOSTNAME").unwrap_or_else(|_| "pi-postman".to_string()),
+            domains,
+            max_size: env_or("SMTP_MAX_SIZE", 25 * 1024 * 1024),
+            tls,
+        }))
+    }
+
+    fn accepts(&self, rcpt: &str) -> bool {
+        rcpt.rsplit_once('@')
+            .is_some_and(|(_, d)| self.domains.iter().any(|x| x.eq_ignore_ascii_case(d)))
+    }
+}
+
+/// SMTP 서버 실행 (연결마다 태스크 하나)
+pub async fn serve(cfg: SmtpConfig) -> Result<()> {
+    let listener = TcpListener::bind(&cfg.addr)
+        .await
+        .with_context(|| format!("SMTP {} 바인드 실패", cfg.addr))?;
+    serve_listener(listener, cfg).await
+}
+
+/// 이미 바인드한 소켓으로 SMTP 서버 실행 (테스트에서 임의 포트 사용)
+pub async fn serve_listener(listener: TcpListener, cfg: SmtpConfig) -> Result<()> {
+    info!(
+        "[SMTP] {} 에서 대기 — 도메인={:?} STARTTLS={}",
+        listener.local_addr().map(|a| a.to_string()).unwrap_or_else(|_| cfg.addr.clone()),
+        cfg.domains,
+        cfg.tls.is_some()
+    );
+    let cfg = Arc::new(cfg);
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("[SMTP] accept 실패: {}", e);
+                continue;
+            }
+        };
+        let cfg = cfg.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, peer, cfg).await {
+                debug!("[SMTP] {} 연결 종료: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, peer: SocketAddr, cfg: Arc<SmtpConfig>) -> Result<()> {
+    stream
+        .write_all(format!("220 {} ESMTP pi-postman\r\n", cfg.hostname).as_bytes())
+        .await?;
+    // 평문 세션에서 STARTTLS 요청이 오면 TLS 로 감싸서 새 세션 시작
+    match run_session(stream, peer, &cfg, false).await? {
+        Some(stream) => {
+            let acceptor = cfg.tls.as_ref().ok_or_else(|| anyhow!("TLS 미설정"))?;
+            let tls = acceptor.accept(stream).await.context("TLS 핸드셰이크 실패")?;
+            run_session(tls, peer, &cfg, true).await?;
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// 봉투 정보 (MAIL FROM ~ DATA)
+#[derive(Default)]
+struct Envelope {
+    helo: Option<String>,
+    from: Option<String>,
+    rcpts: Vec<String>,
+}
+
+/// 명령 루프. STARTTLS 를 받으면 원래 스트림을 돌려줌
+///
+/// STARTTLS 이후에는 RFC 3207 에 따라 이전 상태(EHLO 포함)를 모두 버립니다.
+async fn run_session<S>(stream: S, peer: SocketAddr, cfg: &SmtpConfig, secure: bool) -> Result<Option<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut conn = BufReader::new(stream);
+    let mut env = Envelope::default();
+
+    loop {
+        let Some(line) = read_line(&mut conn, MAX_COMMAND_LINE).await? else {
+            return Ok(None);
+        };
+        let line = String::from_utf8_lossy(&line).trim_end().to_string();
+        let (verb, arg) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+        let verb = verb.to_ascii_uppercase();
+
+        let response = match verb.as_str() {
+            "EHLO" | "HELO" if arg.trim().is_empty() => "501 5.5.4 Hostname required".to_string(),
+            "EHLO" => {
+                env = Envelope { helo: Some(arg.trim().to_string()), ..Default::default() };
+                let mut ext = vec![
+                    cfg.hostname.clone(),
+                    format!("SIZE {}", cfg.max_size),
+                    "8BITMIME".to_string(),
+                    "ENHANCEDSTATUSCODES".to_string(),
+                ];
+                if cfg.tls.is_some() && !secure {
+                    ext.push("STARTTLS".to_string());
+                }
+                multiline(250, &ext)
+            }
+            "HELO" => {
+                env = Envelope { helo: Some(arg.trim().to_string()), ..Default::default() };
+                format!("250 {}", cfg.hostname)
+            }
+            "STARTTLS" if secure || cfg.tls.is_none() => "502 5.5.1 STARTTLS not available".to_string(),
+            "STARTTLS" => {
+                write_reply(&mut conn, "220 2.0.0 Ready to start TLS").await?;
+                // 버퍼에 남은 평문 데이터는 명령 주입 방지를 위해 버림
+                return Ok(Some(conn.into_inner()));
+            }
+            "MAIL" => mail_from(&mut env, arg, cfg),
+            "RCPT" => rcpt_to(&mut env, arg, cfg),
+            "DATA" if env.from.is_none() || env.rcpts.is_empty() => "503 5.5.1 Need MAIL and RCPT first".to_string(),
+            "DATA" => {
+                write_reply(&mut conn, "354 Start mail input; end with <CRLF>.<CRLF>").await?;
+                let reply = receive_data(&mut conn, &env, peer, cfg, secure).await?;
+                env.from = None;
+                env.rcpts.clear();
+                reply
+            }
+            "RSET" => {
+                env.from = None;
+                env.rcpts.clear();
+                "250 2.0.0 OK".to_string()
+            }
+            "NOOP" => "250 2.0.0 OK".to_string(),
+            "VRFY" => "252 2.5.2 Cannot VRFY user".to_string(),
+            "QUIT" => {
+                write_reply(&mut conn, "221 2.0.0 Bye").await?;
+                return Ok(None);
+            }
+            _ => "502 5.5.2 Command not recognized".to_string(),
+        };
+        write_reply(&mut conn, &response).await?;
+    }
+}
+
+fn mail_from(env: &mut Envelope, arg: &str, cfg: &SmtpConfig) -> String {
+    if env.helo.is_none() {
+        return "503 5.5.1 Send EHLO/HELO first".to_string();
+    }
+    if env.from.is_some() {
+        return "503 5.5.1 Sender already specified".to_string();
+    }
+    let Some((addr, params)) = parse_path(arg, "FROM:") else {
+        return "501 5.5.4 Syntax: MAIL FROM:<address>".to_string();
+    };
+    for param in params.split_whitespace() {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        match key.to_ascii_uppercase().as_str() {
+            "SIZE" => {
+                if value.parse::<usize>().is_ok_and(|n| n > cfg.max_size) {
+                    return format!("552 5.3.4 Message size exceeds limit of {} bytes", cfg.max_size);
+                }
+            }
+            "BODY" if value.eq_ignore_ascii_case("8BITMIME") || value.eq_ignore_ascii_case("7BIT") => {}
+            _ => return format!("555 5.5.4 Unsupported parameter: {}", param),
+        }
+    }
+    env.from = Some(addr);
+    "250 2.1.0 OK".to_string()
+}
+
+fn rcpt_to(env: &mut Envelope, arg: &str, cfg: &SmtpConfig) -> String {
+    if env.from.is_none() {
+        return "503 5.5.1 Need MAIL first".to_string();
+    }
+    let Some((addr, _)) = parse_path(arg, "TO:") else {
+        return "501 5.5.4 Syntax: RCPT TO:<address>".to_string();
+    };
+    if !cfg.accepts(&addr) {
+        return "550 5.7.1 Relaying denied".to_string();
+    }
+    if env.rcpts.len() >= MAX_RECIPIENTS {
+        return "452 4.5.3 Too many recipients".to_string();
+    }
+    env.rcpts.push(addr);
+    "250 2.1.5 OK".to_string()
+}
+
+/// "FROM:<a@b> SIZE=10" → ("a@b", "SIZE=10")
+fn parse_path(arg: &str, prefix: &str) -> Option<(String, String)> {
+    let rest = arg.trim();
+    if rest.len() < prefix.len() || !rest[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = rest[prefix.len()..].trim_start();
+    let rest = rest.strip_prefix('<')?;
+    let (addr, params) = rest.split_once('>')?;
+    Some((addr.trim().to_string(), params.trim().to_string()))
+}
+
+/// DATA 본문 수신 후 파이프라인에 전달
+async fn receive_data<S>(
+    conn: &mut BufReader<S>,
+    env: &Envelope,
+    peer: SocketAddr,
+    cfg: &SmtpConfig,
+    secure: bool,
+) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let received = format!(
+        "Received: from {} ({})\r\n\tby {} with {}; {}\r\n",
+        env.helo.as_deref().unwrap_or("unknown"),
+        peer.ip(),
+        cfg.hostname,
+        if secure { "ESMTPS" } else { "ESMTP" },
+        Utc::now().to_rfc2822()
+    );
+    let mut data = received.into_bytes();
+    let mut too_large = false;
+
+    loop {
+        // 크기 초과 후에도 종료 줄까지는 읽어서 버림
+        let Some(line) = read_line(conn, cfg.max_size + 2).await? else {
+            return Err(anyhow!("DATA 도중 연결 끊김"));
+        };
+        if line == b".\r\n" || line == b".\n" {
+            break;
+        }
+        if too_large {
+            continue;
+        }
+        let line = line.strip_prefix(b".").unwrap_or(&line);
+        data.extend_from_slice(line);
+        if data.len() > cfg.max_size {
+            too_large = true;
+            data.clear();
+        }
+    }
+    if too_large {
+        return Ok(format!("552 5.3.4 Message size exceeds limit of {} bytes", cfg.max_size));
+    }
+
+    let size = data.len();
+    // 큰 첨부의 디코딩/텍스트 추출은 오래 걸리므로 블로킹 풀에서 파싱
+    let em = match tokio::task::spawn_blocking(move || parse_single_email(0, &data)).await {
+        Ok(em) => em,
+        Err(e) => {
+            error!("[SMTP] 파싱 실패: {}", e);
+            return Ok("451 4.3.0 Temporary processing error".to_string());
+        }
+    };
+    for w in &em.parse_warnings {
+        warn!("[SMTP] 파싱 경고: {}", w);
+    }
+    info!(
+        "[SMTP] {} → {:?} ({}B) 수신: {}",
+        env.from.as_deref().unwrap_or(""),
+        env.rcpts,
+        size,
+        em.subject
+    );
+    match ingest_parsed(em, "SMTP").await {
+        Ok(id) => Ok(format!("250 2.0.0 OK id={}", id)),
+        Err(e) => {
+            error!("[SMTP] 저장 실패: {}", e);
+            Ok("451 4.3.0 Temporary processing error".to_string())
+        }
+    }
+}
+
+/// 한 줄 읽기 (LF 포함). 연결이 끝났으면 None
+async fn read_line<S>(conn: &mut BufReader<S>, limit: usize) -> Result<Option<Vec<u8>>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut line = Vec::new();
+    let n = tokio::time::timeout(COMMAND_TIMEOUT, (&mut *conn).take(limit as u64).read_until(b'\n', &mut line))
+        .await
+        .map_err(|_| anyhow!("명령 대기 타임아웃"))??;
+    if n == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\n") && line.len() >= limit {
+        return Err(anyhow!("줄이 너무 깁니다"));
+    }
+    Ok(Some(line))
+}
+
+async fn write_reply<S>(conn: &mut BufReader<S>, reply: &str) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let stream = conn.get_mut();
+    stream.write_all(reply.as_bytes()).await?;
+    stream.write_all(b"\r\n").await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// "250-a\r\n250-b\r\n250 c" 형식
+fn multiline(code: u16, lines: &[String]) -> String {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let sep = if i + 1 == lines.len() { ' ' } else { '-' };
+            format!("{}{}{}", code, sep, l)
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
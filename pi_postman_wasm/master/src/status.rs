@@ -0,0 +1,75 @@
+// master/src/status.rs
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    static ref ACCOUNT_STATUS: Mutex<BTreeMap<String, AccountStatus>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// 계정별 모니터링 상태 (로그 + /api/accounts 용)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AccountStatus {
+    pub name: String,
+    pub email: String,
+    /// connecting / polling / idle / error
+    pub state: String,
+    pub folders: Vec<String>,
+    pub last_poll_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub consecutive_failures: u32,
+    /// 이번 프로세스에서 가져온 메일 수
+    pub fetched: u64,
+}
+
+/// 계정 상태 등록 (시작 시 1회)
+pub fn register(name: &str, email: &str, folders: &[String]) {
+    update(name, |s| {
+        s.email = email.to_string();
+        s.folders = folders.to_vec();
+        s.state = "connecting".to_string();
+    });
+}
+
+/// 계정 상태 갱신 (없으면 생성)
+pub fn update(name: &str, f: impl FnOnce(&mut AccountStatus)) {
+    if let Ok(mut map) = ACCOUNT_STATUS.lock() {
+        let entry = map.entry(name.to_string()).or_insert_with(|| AccountStatus {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        f(entry);
+    }
+}
+
+/// 오류 기록
+pub fn record_error(name: &str, error: &str) {
+    update(name, |s| {
+        s.state = "error".to_string();
+        s.last_error = Some(error.to_string());
+        s.last_error_at = Some(Utc::now());
+        s.consecutive_failures += 1;
+    });
+}
+
+/// 정상 폴링 완료 기록
+pub fn record_poll(name: &str, fetched: usize) {
+    update(name, |s| {
+        s.state = "idle".to_string();
+        s.last_poll_at = Some(Utc::now());
+        s.consecutive_failures = 0;
+        s.fetched += fetched as u64;
+    });
+}
+
+/// 전체 계정 상태 스냅샷
+pub fn snapshot() -> Vec<AccountStatus> {
+    ACCOUNT_STATUS
+        .lock()
+        .map(|map| map.values().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,57 @@
+// master/tests/error.rs
+//
+// 추출기 거부가 사유에 맞는 상태 코드의 problem+json 으로 나가는지 확인
+
+use axum::{extract::DefaultBodyLimit, routing::post, Router};
+use master::error::ApiJson;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::net::TcpListener;
+
+#[derive(Deserialize)]
+struct Body {
+    #[allow(dead_code)]
+    n: u32,
+}
+
+async fn handler(ApiJson(_): ApiJson<Body>) -> &'static str {
+    "ok"
+}
+
+async fn start() -> String {
+    let app = Router::new().route("/x", post(handler).layer(DefaultBodyLimit::max(64)));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/x", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+/// (상태 코드, problem code)
+async fn post_json(url: &str, content_type: Option<&str>, body: String) -> (u16, String) {
+    let mut req = reqwest::Client::new().post(url).body(body);
+    if let Some(ct) = content_type {
+        req = req.header("content-type", ct);
+    }
+    let res = req.send().await.unwrap();
+    let status = res.status().as_u16();
+    if status == 200 {
+        return (status, String::new());
+    }
+    assert_eq!(res.headers()["content-type"], "application/problem+json");
+    let problem: Value = res.json().await.unwrap();
+    assert_eq!(problem["status"], status);
+    (status, problem["code"].as_str().unwrap_or_default().to_string())
+}
+
+#[tokio::test]
+async fn json_rejections_keep_their_status() {
+    let url = start().await;
+    let json = Some("application/json");
+    assert_eq!(post_json(&url, json, r#"{"n":1}"#.into()).await.0, 200);
+    assert_eq!(post_json(&url, json, "{not json".into()).await, (400, "bad_request".into()));
+    assert_eq!(post_json(&url, json, r#"{"n":"x"}"#.into()).await, (422, "validation_failed".into()));
+    assert_eq!(post_json(&url, None, r#"{"n":1}"#.into()).await, (415, "unsupported_media_type".into()));
+    assert_eq!(post_json(&url, Some("text/plain"), r#"{"n":1}"#.into()).await, (415, "unsupported_media_type".into()));
+    let big = format!(r#"{{"n":1,"pad":"{}"}}"#, "x".repeat(1024));
+    assert_eq!(post_json(&url, json, big).await, (413, "payload_too_large".into()));
+}
@@ -0,0 +1,222 @@
+// master/tests/smtp.rs
+//
+// lettre 클라이언트로 내장 SMTP 수신 서버를 구동해 보는 테스트
+
+use common::email::get_email;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use master::smtp::{serve_listener, SmtpConfig};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_native_tls::{TlsAcceptor, TlsConnector};
+
+async fn start(max_size: usize) -> u16 {
+    start_with(max_size, None).await
+}
+
+/// 테스트용 자체 서명 인증서 (CN=localhost, 127.0.0.1)
+fn test_acceptor() -> TlsAcceptor {
+    let cert = include_bytes!("fixtures/smtp_cert.pem");
+    let key = include_bytes!("fixtures/smtp_key.pem");
+    let identity = native_tls::Identity::from_pkcs8(cert, key).unwrap();
+    TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap())
+}
+
+async fn start_with(max_size: usize, tls: Option<TlsAcceptor>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let cfg = SmtpConfig {
+        addr: format!("127.0.0.1:{}", port),
+        hostname: "mx.test".to_string(),
+        domains: vec!["example.com".to_string()],
+        max_size,
+        tls,
+    };
+    tokio::spawn(serve_listener(listener, cfg));
+    port
+}
+
+fn mailer(port: u16) -> AsyncSmtpTransport<Tokio1Executor> {
+    AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+        .port(port)
+        .hello_name(ClientId::Domain("client.test".to_string()))
+        .build()
+}
+
+fn message(to: &str, subject: &str, body: &str) -> Message {
+    Message::builder()
+        .from("홍길동 <hong@sender.test>".parse().unwrap())
+        .to(to.parse().unwrap())
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_string())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn delivers_message_into_store() {
+    let port = start(1024 * 1024).await;
+    let body = "첫 줄\r\n.점으로 시작하는 줄\r\n끝";
+    let resp = mailer(port)
+        .send(message("inbox@example.com", "SMTP 테스트", body))
+        .await
+        .unwrap();
+    assert!(resp.is_positive());
+    let text = resp.message().collect::<Vec<_>>().join(" ");
+    let id = text.strip_prefix("2.0.0 OK id=").unwrap_or_else(|| panic!("응답: {}", text));
+
+    let email = get_email(id).unwrap();
+    assert_eq!(email.subject, "SMTP 테스트");
+    assert!(email.from.contains("hong@sender.test"), "{}", email.from);
+    assert!(email.body.contains(".점으로 시작하는 줄"), "{}", email.body);
+    assert!(!email.body.contains("..점으로"), "{}", email.body);
+}
+
+#[tokio::test]
+async fn rejects_relay_to_foreign_domain() {
+    let port = start(1024 * 1024).await;
+    let err = mailer(port)
+        .send(message("someone@elsewhere.test", "relay", "x"))
+        .await
+        .unwrap_err();
+    assert!(err.is_permanent(), "{}", err);
+    assert!(err.to_string().contains("Relaying denied"), "{}", err);
+}
+
+#[tokio::test]
+async fn rejects_oversized_message() {
+    let port = start(256).await;
+    let err = mailer(port)
+        .send(message("inbox@example.com", "big", &"x".repeat(1024)))
+        .await
+        .unwrap_err();
+    assert!(err.is_permanent(), "{}", err);
+    assert!(err.to_string().contains("exceeds limit"), "{}", err);
+}
+
+/// 응답 문구는 클라이언트/로그 호환을 위해 ASCII 만 사용
+#[tokio::test]
+async fn replies_are_ascii() {
+    let port = start(1024 * 1024).await;
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut conn = BufReader::new(stream);
+    let mut replies = Vec::new();
+    let mut line = String::new();
+    conn.read_line(&mut line).await.unwrap();
+    replies.push(line.clone());
+
+    for cmd in ["HELO", "MAIL FROM:<a@b.test>", "EHLO client.test", "RCPT TO:<x@y.test>", "DATA", "VRFY x", "BOGUS", "QUIT"] {
+        conn.get_mut().write_all(format!("{}\r\n", cmd).as_bytes()).await.unwrap();
+        // 여러 줄 응답은 "250 " 처럼 공백이 붙은 줄에서 끝남
+        loop {
+            line.clear();
+            conn.read_line(&mut line).await.unwrap();
+            replies.push(line.clone());
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+    }
+    // 인사말 + 명령 8개 (EHLO 는 4줄)
+    assert_eq!(replies.len(), 12, "{:?}", replies);
+    for reply in &replies {
+        assert!(reply.is_ascii(), "ASCII 가 아닌 응답: {:?}", reply);
+    }
+    assert!(replies.last().unwrap().starts_with("221 "), "{:?}", replies);
+}
+
+#[tokio::test]
+async fn delivers_message_over_starttls() {
+    let port = start_with(1024 * 1024, Some(test_acceptor())).await;
+    let tls = TlsParameters::builder("localhost".to_string())
+        .dangerous_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let mailer = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+        .port(port)
+        .hello_name(ClientId::Domain("client.test".to_string()))
+        .tls(Tls::Required(tls))
+        .build();
+    let resp = mailer
+        .send(message("inbox@example.com", "STARTTLS 테스트", "암호화 구간"))
+        .await
+        .unwrap();
+    let text = resp.message().collect::<Vec<_>>().join(" ");
+    let id = text.strip_prefix("2.0.0 OK id=").unwrap_or_else(|| panic!("응답: {}", text));
+
+    let email = get_email(id).unwrap();
+    assert_eq!(email.subject, "STARTTLS 테스트");
+    let received = email
+        .headers
+        .raw
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Received"))
+        .map(|(_, value)| value.clone())
+        .unwrap_or_default();
+    assert!(received.contains("with ESMTPS"), "{}", received);
+}
+
+/// 명령 하나를 보내고 (여러 줄이면 마지막 줄까지) 응답 줄들을 돌려줌
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(conn: &mut BufReader<S>, cmd: &str) -> Vec<String> {
+    conn.get_mut().write_all(format!("{}\r\n", cmd).as_bytes()).await.unwrap();
+    read_reply(conn).await
+}
+
+async fn read_reply<S: AsyncRead + Unpin>(conn: &mut BufReader<S>) -> Vec<String> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        conn.read_line(&mut line).await.unwrap();
+        let last = line.as_bytes().get(3) != Some(&b'-');
+        lines.push(line);
+        if last {
+            return lines;
+        }
+    }
+}
+
+/// STARTTLS 뒤에 붙여 보낸 평문 명령은 버리고, TLS 세션은 EHLO 부터 새로 시작
+#[tokio::test]
+async fn starttls_discards_plaintext_state() {
+    let port = start_with(1024 * 1024, Some(test_acceptor())).await;
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut conn = BufReader::new(stream);
+    read_reply(&mut conn).await;
+    let ehlo = command(&mut conn, "EHLO client.test").await;
+    assert!(ehlo.iter().any(|l| l.contains("STARTTLS")), "{:?}", ehlo);
+
+    // 핸드셰이크 전에 평문으로 끼워 넣은 MAIL FROM
+    let reply = command(&mut conn, "STARTTLS\r\nMAIL FROM:<evil@attacker.test>").await;
+    assert!(reply[0].starts_with("220 "), "{:?}", reply);
+
+    let connector = TlsConnector::from(
+        native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap(),
+    );
+    let tls = connector.connect("localhost", conn.into_inner()).await.unwrap();
+    let mut conn = BufReader::new(tls);
+
+    let reply = command(&mut conn, "RCPT TO:<inbox@example.com>").await;
+    assert!(reply[0].starts_with("503 "), "끼워 넣은 MAIL FROM 이 남아 있음: {:?}", reply);
+    let ehlo = command(&mut conn, "EHLO client.test").await;
+    assert!(ehlo[0].starts_with("250"), "{:?}", ehlo);
+    assert!(!ehlo.iter().any(|l| l.contains("STARTTLS")), "{:?}", ehlo);
+    let reply = command(&mut conn, "STARTTLS").await;
+    assert!(reply[0].starts_with("502 "), "{:?}", reply);
+}
+
+#[tokio::test]
+async fn starttls_not_offered_without_certificate() {
+    let port = start(1024 * 1024).await;
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut conn = BufReader::new(stream);
+    read_reply(&mut conn).await;
+    let ehlo = command(&mut conn, "EHLO client.test").await;
+    assert!(!ehlo.iter().any(|l| l.contains("STARTTLS")), "{:?}", ehlo);
+    let reply = command(&mut conn, "STARTTLS").await;
+    assert!(reply[0].starts_with("502 "), "{:?}", reply);
+}